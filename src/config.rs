@@ -1,3 +1,4 @@
+use crate::domain::{Canonicalization, HashAlgorithm, HashScheme};
 use anyhow::Result;
 use config::{Config, Environment, File};
 use serde::Deserialize;
@@ -8,16 +9,174 @@ pub struct AppConfig {
     pub port: u16,
     pub queue_size: usize,
     pub batch_size: usize,
+    pub batch_max_wait_ms: u64,
+    pub ingest: IngestConfig,
+    pub syslog: SyslogConfig,
+    pub channel: ChannelConfig,
+    pub retry: RetryConfig,
+    pub hasher: HasherConfig,
+    pub chain: ChainConfig,
+    pub transparency: TransparencyConfig,
+    pub signer: SignerConfig,
+    pub aggregator: AggregatorConfig,
+    pub storage: StorageConfig,
     pub ethereum: EthereumConfig,
+    pub file_signer: FileSignerConfig,
+    pub tsa: TsaConfig,
     pub elastic: ElasticConfig,
+    pub correlation: CorrelationConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IngestConfig {
+    pub timeout_ms: u64,
+    pub retry_after_secs: u64,
+    pub max_body_bytes: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SyslogConfig {
+    pub udp: Vec<String>,
+    pub tcp: Vec<String>,
+    pub max_message_bytes: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChannelConfig {
+    pub backend: ChannelBackend,
+    pub path: String,
+    pub segment_size: u64,
+    pub sync: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelBackend {
+    Memory,
+    Disk,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub dead_letter_path: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HasherConfig {
+    pub algorithm: HashAlgorithm,
+    pub scheme: HashScheme,
+    pub canonicalization: Canonicalization,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChainConfig {
+    pub stream: String,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransparencyConfig {
+    pub path: String,
+    pub publish_interval_secs: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignerConfig {
+    pub backend: SignerBackend,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerBackend {
+    Ethereum,
+    File,
+    Rfc3161,
+    Memory,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AggregatorConfig {
+    pub enabled: bool,
+    pub window_ms: u64,
+    pub max_batches: usize,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Elastic,
+    Memory,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct EthereumConfig {
     pub url: String,
     pub contract: String,
+    pub deployment_block: u64,
     pub private_key: String,
     pub max_tx_pending: usize,
+    pub stuck_after_secs: u64,
+    pub fee_bump_percent: u64,
+    pub fee_strategy: FeeStrategyKind,
+    pub fee_percentile: f64,
+    pub fee_history_blocks: u64,
+    pub max_fee_gwei: f64,
+    pub priority_fee_gwei: f64,
+    pub daily_budget_eth: f64,
+    pub budget_path: String,
+    pub finality: FinalityKind,
+    pub confirmations: u64,
+    pub confirmation_timeout_secs: u64,
+    pub receipts_path: String,
+    pub sent_path: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeStrategyKind {
+    Estimate,
+    Percentile,
+    Fixed,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FinalityKind {
+    Depth,
+    Finalized,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FileSignerConfig {
+    pub path: String,
+    pub key_path: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TsaConfig {
+    pub mode: TsaMode,
+    pub url: String,
+    pub certificate: String,
+    pub key: String,
+    pub policy: String,
+    pub path: String,
+    pub timeout_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TsaMode {
+    Local,
+    Remote,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -26,6 +185,44 @@ pub struct ElasticConfig {
     pub username: String,
     pub password: String,
     pub indices_pattern: String,
+    pub status_index: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CorrelationConfig {
+    pub type_field: String,
+    pub time_field: String,
+    pub firewall: FirewallHopConfig,
+    pub dhcp: DhcpHopConfig,
+    pub radius: RadiusHopConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FirewallHopConfig {
+    pub kind: String,
+    pub mapped_ip_field: String,
+    pub mapped_port_field: String,
+    pub ip_field: String,
+    pub before_secs: i64,
+    pub after_secs: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DhcpHopConfig {
+    pub kind: String,
+    pub ip_field: String,
+    pub mac_field: String,
+    pub before_secs: i64,
+    pub after_secs: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RadiusHopConfig {
+    pub kind: String,
+    pub mac_field: String,
+    pub mac_separator: String,
+    pub before_secs: i64,
+    pub after_secs: i64,
 }
 
 impl AppConfig {
@@ -1,11 +1,17 @@
 use crate::{
     config::AppConfig,
-    domain::{DynHasher, DynSignerRepository, DynStorageRepository, DynUuidGenerator, Pipeline},
-    factories::{make_hasher, make_pipeline, make_signer_repository, make_storage_repository, make_uuid_generator},
-    infra::prometheus::Prometheus,
+    domain::{
+        ChannelError, Document, DynBatchStatusRepository, DynDeadLetterRepository, DynHasher, DynSignerRepository, DynStorageRepository,
+        DynTransparencyLog, DynUuidGenerator, Pipeline,
+    },
+    factories::{
+        make_batch_status_repository, make_chain_head_repository, make_chain_linker, make_dead_letter_repository, make_hasher,
+        make_pipeline, make_retry_tracker, make_signer_repository, make_storage_repository, make_transparency_log, make_uuid_generator,
+    },
+    infra::{chain::ChainLinker, prometheus::Prometheus, retry::RetryTracker},
 };
 use anyhow::Result;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 #[derive(Clone)]
 pub struct Context {
@@ -13,6 +19,11 @@ pub struct Context {
     pub pipeline: Pipeline,
     pub signer: DynSignerRepository,
     pub storage: DynStorageRepository,
+    pub status: DynBatchStatusRepository,
+    pub dead_letters: DynDeadLetterRepository,
+    pub retries: RetryTracker,
+    pub chain: ChainLinker,
+    pub log: DynTransparencyLog,
     pub hasher: DynHasher,
     pub uuid: DynUuidGenerator,
     pub prom: Prometheus,
@@ -21,13 +32,60 @@ pub struct Context {
 impl Context {
     pub fn init() -> Result<Arc<Self>> {
         let config = AppConfig::init().unwrap();
-        let pipeline = make_pipeline(config.queue_size);
-        let hasher = make_hasher();
+        let pipeline = make_pipeline(&config)?;
+        let hasher = make_hasher(&config);
         let uuid = make_uuid_generator();
-        let signer = make_signer_repository(&config)?;
-        let storage = make_storage_repository(&config, hasher.clone())?;
         let prom = Prometheus::new();
+        let signer = make_signer_repository(&config, &prom)?;
+        let storage = make_storage_repository(&config, hasher.clone())?;
+        let status = make_batch_status_repository(&config)?;
+        let dead_letters = make_dead_letter_repository(&config)?;
+        let retries = make_retry_tracker(&config);
+        let chain = make_chain_linker(&config, make_chain_head_repository(&config)?, hasher.clone());
+        let log = make_transparency_log(&config)?;
+
+        prom.worker_queue_size.set(pipeline.worker.pending() as f64);
+        prom.signer_queue_size.set(pipeline.signer.pending() as f64);
+        prom.storage_queue_size.set(pipeline.storage.pending() as f64);
+
+        Ok(Arc::new(Self { config, pipeline, signer, storage, status, dead_letters, retries, chain, log, hasher, uuid, prom }))
+    }
+}
+
+impl Context {
+    /// Enqueues a document for batching, giving up after the configured ingest
+    /// timeout instead of waiting for room in a full worker queue.
+    pub async fn ingest(&self, document: Document) -> std::result::Result<(), ChannelError> {
+        let timeout = Duration::from_millis(self.config.ingest.timeout_ms);
+        if let Err(err) = self.pipeline.worker.send_timeout(document, timeout).await {
+            self.prom.docs_rejected_total.inc();
+            return Err(err);
+        }
+        self.prom.docs_total.inc();
+        self.prom.worker_queue_size.inc();
+        Ok(())
+    }
+
+    /// Enqueues a whole request's documents or none of them, so a shipper that retries
+    /// a rejected request does not ingest its first documents twice.
+    pub async fn ingest_all(&self, documents: Vec<Document>) -> std::result::Result<(), ChannelError> {
+        let timeout = Duration::from_millis(self.config.ingest.timeout_ms);
+        let count = documents.len();
+        if let Err(err) = self.pipeline.worker.send_all_timeout(documents, timeout).await {
+            self.prom.docs_rejected_total.inc_by(count as f64);
+            return Err(err);
+        }
+        self.prom.docs_total.inc_by(count as f64);
+        self.prom.worker_queue_size.add(count as f64);
+        Ok(())
+    }
 
-        Ok(Arc::new(Self { config, pipeline, signer, storage, hasher, uuid, prom }))
+    /// Enqueues a document, waiting as long as it takes for room in the worker queue.
+    /// Meant for stream sources whose senders can be slowed down instead of refused.
+    pub async fn ingest_wait(&self, document: Document) -> Result<()> {
+        self.pipeline.worker.send(document).await?;
+        self.prom.docs_total.inc();
+        self.prom.worker_queue_size.inc();
+        Ok(())
     }
 }
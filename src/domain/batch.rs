@@ -1,9 +1,70 @@
-use crate::domain::Document;
+use crate::domain::{ChainLink, Document};
 use serde::{Deserialize, Serialize};
 
+/// How documents are serialized before hashing.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Canonicalization {
+    /// `serde_json::to_string`, as hashed before canonicalization was introduced.
+    #[default]
+    Legacy,
+    /// RFC 8785 JSON Canonicalization Scheme.
+    Jcs,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    #[serde(rename = "sha256")]
+    Sha256,
+    #[serde(rename = "sha3-256")]
+    Sha3_256,
+    #[serde(rename = "keccak256")]
+    Keccak256,
+    #[serde(rename = "blake3")]
+    Blake3,
+}
+
+/// How the per-document hashes are combined into the batch digest.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashScheme {
+    /// Sequential hash chain over the whole batch.
+    #[default]
+    Chain,
+    /// RFC 6962 Merkle tree, allowing per-document inclusion proofs.
+    Merkle,
+}
+
+/// Everything needed to recompute a batch digest. Recorded with every batch so it
+/// still verifies after the configured hashing changes; the defaults describe
+/// batches hashed before any of this was recorded.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HashSpec {
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    #[serde(default)]
+    pub scheme: HashScheme,
+    #[serde(default)]
+    pub canonicalization: Canonicalization,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Batch {
     pub id: String,
     pub documents: Vec<Document>,
+    /// Anchored digest: the content digest, bound to `link` when the batch is chained.
+    pub digest: [u8; 32],
+    #[serde(flatten)]
+    pub hash: HashSpec,
+    #[serde(default)]
+    pub link: Option<ChainLink>,
+}
+
+/// Digest recomputed from storage without keeping the documents around.
+#[derive(Debug, Clone)]
+pub struct StoredDigest {
     pub digest: [u8; 32],
+    pub hash: HashSpec,
+    pub count: usize,
 }
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Position of a batch in its stream. The anchored digest of a linked batch covers
+/// its content digest together with this link, so it commits to every batch before it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChainLink {
+    pub stream: String,
+    pub sequence: u64,
+    /// Anchored digest of the batch at `sequence - 1`; `None` for the first batch.
+    #[serde(with = "hex_digest")]
+    pub previous: Option<[u8; 32]>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Latest batch linked into a stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainHead {
+    pub stream: String,
+    pub sequence: u64,
+    pub digest: [u8; 32],
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkedBatch {
+    pub id: String,
+    pub link: ChainLink,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainIssueKind {
+    /// Sequence numbers are skipped: batches were deleted.
+    Missing,
+    /// Two batches claim the same sequence number: one was injected.
+    Duplicate,
+    /// A batch does not point at the digest of its predecessor.
+    BrokenLink,
+    /// A batch is older than the batch it follows.
+    Reordered,
+    /// The digest recomputed from storage differs from the anchored one.
+    DigestMismatch,
+    /// Storage holds fewer batches than the persisted chain head.
+    Truncated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainIssue {
+    pub kind: ChainIssueKind,
+    pub batch_id: Option<String>,
+    pub sequence: u64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainReport {
+    pub stream: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub batches: usize,
+    pub first_sequence: Option<u64>,
+    pub last_sequence: Option<u64>,
+    pub verified: bool,
+    pub issues: Vec<ChainIssue>,
+}
+
+mod hex_digest {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(digest: &Option<[u8; 32]>, serializer: S) -> Result<S::Ok, S::Error> {
+        match digest {
+            Some(digest) => serializer.serialize_some(&hex::encode(digest)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<[u8; 32]>, D::Error> {
+        let Some(encoded) = Option::<String>::deserialize(deserializer)? else { return Ok(None) };
+        let mut digest = [0u8; 32];
+        hex::decode_to_slice(encoded, &mut digest).map_err(D::Error::custom)?;
+        Ok(Some(digest))
+    }
+}
@@ -0,0 +1,39 @@
+use crate::domain::Document;
+use serde::Serialize;
+
+/// Outcome of tracing a public `ip:port` back to a subscriber through firewall NAT,
+/// DHCP lease and RADIUS session logs. Hops stop at the first one that cannot be found.
+#[derive(Debug, Clone, Serialize)]
+pub struct Attribution {
+    pub attributed: bool,
+    pub verified: bool,
+    pub firewall: Option<Hop>,
+    pub dhcp: Option<Hop>,
+    pub radius: Option<Hop>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hop {
+    pub batch_id: String,
+    pub document: Document,
+    pub verification: Verification,
+}
+
+/// A hop is verified when its document is part of the stored batch and the batch
+/// digest recomputed from storage matches the digest anchored by the signer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    pub included: bool,
+    pub storage_digest: Option<String>,
+    pub signer_digest: Option<String>,
+    pub verified: bool,
+}
+
+impl Attribution {
+    pub fn new(firewall: Option<Hop>, dhcp: Option<Hop>, radius: Option<Hop>) -> Self {
+        let hops = [&firewall, &dhcp, &radius];
+        let attributed = hops.iter().all(|hop| hop.is_some());
+        let verified = attributed && hops.iter().flat_map(|hop| hop.as_ref()).all(|hop| hop.verification.verified);
+        Self { attributed, verified, firewall, dhcp, radius }
+    }
+}
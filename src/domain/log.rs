@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+
+/// Leaves appended to the transparency log for one batch: its documents, in
+/// `audita_ord` order, occupy the leaves `[start, start + count)`.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub batch_id: String,
+    pub start: u64,
+    pub count: u64,
+}
+
+/// Signed tree head: the root of the first `tree_size` leaves of the log at
+/// `timestamp`. It is anchored through the signer as the batch `sth-{tree_size}`
+/// with `digest` as its digest.
+#[derive(Debug, Clone)]
+pub struct TreeHead {
+    pub tree_size: u64,
+    pub root: [u8; 32],
+    pub timestamp: DateTime<Utc>,
+    pub digest: [u8; 32],
+}
+
+impl TreeHead {
+    pub fn batch_id(&self) -> String {
+        format!("sth-{}", self.tree_size)
+    }
+}
@@ -1,11 +1,25 @@
 mod batch;
+mod chain;
+mod correlation;
 mod document;
+mod log;
 mod pipeline;
+mod proof;
 mod protocols;
+mod retry;
 mod search;
+mod signer;
+mod status;
 
 pub use batch::*;
+pub use chain::*;
+pub use correlation::*;
 pub use document::*;
+pub use log::*;
 pub use pipeline::*;
+pub use proof::*;
 pub use protocols::*;
+pub use retry::*;
 pub use search::*;
+pub use signer::*;
+pub use status::*;
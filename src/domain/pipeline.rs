@@ -1,5 +1,45 @@
-use crate::domain::{Batch, Document, DynChannel};
-use std::sync::Arc;
+use crate::domain::{Batch, Document, DynChannel, Stage};
+use std::{fmt, sync::Arc};
+
+/// An item handed out by a [`Channel`](crate::domain::Channel). The offset must be
+/// acknowledged once the item has been fully processed, so durable channels can
+/// forget it instead of replaying it on the next start.
+#[derive(Debug, Clone)]
+pub struct Delivery<T> {
+    pub offset: u64,
+    pub item: T,
+}
+
+#[derive(Debug)]
+pub enum ChannelError {
+    /// The channel stayed at capacity for the whole send deadline.
+    Full,
+    Closed,
+    /// More items were sent at once than the channel can ever hold.
+    TooLarge {
+        capacity: usize,
+    },
+    Failed(anyhow::Error),
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelError::Full => write!(f, "channel is full"),
+            ChannelError::Closed => write!(f, "channel is closed"),
+            ChannelError::TooLarge { capacity } => write!(f, "more items than the channel capacity of {}", capacity),
+            ChannelError::Failed(err) => write!(f, "channel send failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ChannelError {}
+
+impl From<anyhow::Error> for ChannelError {
+    fn from(err: anyhow::Error) -> Self {
+        ChannelError::Failed(err)
+    }
+}
 
 #[derive(Clone)]
 pub struct Pipeline {
@@ -12,4 +52,13 @@ impl Pipeline {
     pub fn new(worker: DynChannel<Document>, signer: DynChannel<Arc<Batch>>, storage: DynChannel<Arc<Batch>>) -> Self {
         Self { worker, signer, storage }
     }
+
+    /// Queue a stage takes batches from, or `None` for stages without a queue of their own.
+    pub fn stage(&self, stage: Stage) -> Option<&DynChannel<Arc<Batch>>> {
+        match stage {
+            Stage::Signer => Some(&self.signer),
+            Stage::Storage => Some(&self.storage),
+            Stage::Worker | Stage::Log => None,
+        }
+    }
 }
@@ -0,0 +1,37 @@
+use crate::domain::Document;
+
+/// Merkle inclusion proof (RFC 6962 audit path) for the leaf at `index` of a tree of
+/// `tree_size` leaves. Siblings are ordered from the leaf up to the root.
+#[derive(Debug, Clone)]
+pub struct InclusionProof {
+    pub index: usize,
+    pub tree_size: usize,
+    pub leaf: [u8; 32],
+    pub path: Vec<[u8; 32]>,
+    pub root: [u8; 32],
+}
+
+/// Proof for a document of a `chain` batch: the partial digest of the documents
+/// before it and the documents after it, from which the batch digest is recomputed.
+#[derive(Debug, Clone)]
+pub struct ChainProof {
+    pub previous: Option<[u8; 32]>,
+    pub following: Vec<Document>,
+    pub digest: [u8; 32],
+}
+
+#[derive(Debug, Clone)]
+pub enum DocumentProof {
+    Merkle(InclusionProof),
+    Chain(ChainProof),
+}
+
+impl DocumentProof {
+    /// Batch content digest the proof leads to.
+    pub fn digest(&self) -> [u8; 32] {
+        match self {
+            DocumentProof::Merkle(proof) => proof.root,
+            DocumentProof::Chain(proof) => proof.digest,
+        }
+    }
+}
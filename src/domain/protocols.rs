@@ -1,29 +1,137 @@
-use crate::domain::{Batch, Document, Query, QueryResult};
+use crate::domain::{
+    AggregateReceipt, AnchorReceipt, Batch, BatchEvent, BatchStatus, ChainHead, ChainLink, ChannelError, DeadLetter, Delivery, Document,
+    DocumentProof, HashSpec, LinkedBatch, LogEntry, Query, QueryResult, SignerAudit, Stage, StoredDigest, TreeHead,
+};
 use anyhow::Result;
 use async_trait::async_trait;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use std::{sync::Arc, time::Duration};
 
 #[async_trait]
 pub trait SignerRepository: Send + Sync {
     async fn publish(&self, batch: &Batch) -> Result<()>;
-    async fn digest(&self, id: &String) -> Result<Option<[u8; 32]>>;
+    async fn digest(&self, id: &str) -> Result<Option<[u8; 32]>>;
+    /// Checks the records the signer keeps locally for tampering, or `None` when the
+    /// backend keeps none.
+    async fn audit(&self) -> Result<Option<SignerAudit>> {
+        Ok(None)
+    }
+    /// DER TimeStampResp anchoring a batch, or `None` when the backend issues no
+    /// RFC 3161 tokens.
+    async fn token(&self, _id: &str) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+    /// Receipt placing a batch in the aggregate anchored for it, or `None` when the
+    /// batch was anchored on its own.
+    async fn receipt(&self, _id: &str) -> Result<Option<AggregateReceipt>> {
+        Ok(None)
+    }
+    /// Transaction that anchored a batch, or `None` when the backend anchors no
+    /// transactions or the batch has none on record.
+    async fn anchor(&self, _id: &str) -> Result<Option<AnchorReceipt>> {
+        Ok(None)
+    }
+    /// How many batches may be published at once.
+    fn concurrency(&self) -> usize {
+        1
+    }
 }
 
 #[async_trait]
 pub trait StorageRepository: Send + Sync {
     async fn store(&self, batch: &Batch) -> Result<()>;
-    async fn retrieve(&self, id: &String) -> Result<Option<Batch>>;
+    async fn retrieve(&self, id: &str) -> Result<Option<Batch>>;
+    /// Recomputes a batch digest by streaming its documents, for batches too large to retrieve.
+    async fn digest(&self, id: &str) -> Result<Option<StoredDigest>>;
+    /// Chained batches of `stream` created within `[from, to]`, ordered by sequence.
+    async fn links(&self, stream: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<LinkedBatch>>;
+    /// The chained batch of `stream` at `sequence`, whenever it was created.
+    async fn link(&self, stream: &str, sequence: u64) -> Result<Option<LinkedBatch>>;
     async fn search(&self, query: &Query) -> Result<QueryResult>;
 }
 
+#[async_trait]
+pub trait BatchStatusRepository: Send + Sync {
+    async fn create(&self, status: &BatchStatus) -> Result<()>;
+    async fn update(&self, id: &str, event: BatchEvent) -> Result<BatchStatus>;
+    async fn get(&self, id: &str) -> Result<Option<BatchStatus>>;
+}
+
+#[async_trait]
+pub trait DeadLetterRepository: Send + Sync {
+    async fn put(&self, letter: &DeadLetter) -> Result<()>;
+    async fn list(&self) -> Result<Vec<DeadLetter>>;
+    async fn get(&self, stage: Stage, id: &str) -> Result<Option<DeadLetter>>;
+    /// Removes a dead letter, returning whether it was still there.
+    async fn remove(&self, stage: Stage, id: &str) -> Result<bool>;
+}
+
 #[async_trait]
 pub trait Channel<T>: Send + Sync {
-    async fn send(&self, item: T);
-    async fn recv(&self) -> Option<T>;
+    async fn send(&self, item: T) -> Result<()>;
+    async fn send_timeout(&self, item: T, timeout: Duration) -> std::result::Result<(), ChannelError>;
+    /// Enqueues every item or none of them, waiting at most `timeout` for room for
+    /// all of them at once.
+    async fn send_all_timeout(&self, items: Vec<T>, timeout: Duration) -> std::result::Result<(), ChannelError>;
+    async fn recv(&self) -> Option<Delivery<T>>;
+    async fn ack(&self, offset: u64) -> Result<()>;
+    fn pending(&self) -> usize;
+}
+
+/// Batch digest computed one document at a time.
+pub trait DigestStream: Send {
+    fn update(&mut self, doc: &Document) -> Result<()>;
+    fn finalize(self: Box<Self>) -> [u8; 32];
 }
 
 pub trait Hasher: Send + Sync {
-    fn digest(&self, docs: &Vec<Document>) -> Result<[u8; 32]>;
+    /// Hashing applied to new batches.
+    fn spec(&self) -> HashSpec;
+    fn stream(&self, spec: &HashSpec) -> Box<dyn DigestStream>;
+    fn digest(&self, docs: &[Document], spec: &HashSpec) -> Result<[u8; 32]> {
+        let mut stream = self.stream(spec);
+        for doc in docs {
+            stream.update(doc)?;
+        }
+        Ok(stream.finalize())
+    }
+    /// Proves that `docs[index]` is part of the batch digest, or `None` when `index`
+    /// is out of range.
+    fn proof(&self, docs: &[Document], index: usize, spec: &HashSpec) -> Result<Option<DocumentProof>>;
+    /// Checks that `doc` with `proof` leads to the digest the proof claims.
+    fn verify(&self, doc: &Document, proof: &DocumentProof, spec: &HashSpec) -> Result<bool>;
+    /// Binds a content digest to its position in the chain.
+    fn link(&self, spec: &HashSpec, content: &[u8; 32], link: &ChainLink) -> [u8; 32];
+}
+
+#[async_trait]
+pub trait ChainHeadRepository: Send + Sync {
+    async fn load(&self, stream: &str) -> Result<Option<ChainHead>>;
+    async fn save(&self, head: &ChainHead) -> Result<()>;
+}
+
+/// Append-only Merkle tree (RFC 6962) over every document ever batched, shared by
+/// all batches so auditors can check that history is only ever extended.
+#[async_trait]
+pub trait TransparencyLog: Send + Sync {
+    /// Hashing of leaves and nodes, fixed when the log is created.
+    fn spec(&self) -> HashSpec;
+    /// Appends the documents of a batch as consecutive leaves. Appending the same
+    /// batch again returns its existing entry.
+    async fn append(&self, batch_id: &str, docs: &[Document]) -> Result<LogEntry>;
+    async fn size(&self) -> Result<u64>;
+    async fn entry(&self, batch_id: &str) -> Result<Option<LogEntry>>;
+    async fn leaf(&self, index: u64) -> Result<Option<[u8; 32]>>;
+    async fn root(&self, tree_size: u64) -> Result<[u8; 32]>;
+    async fn inclusion(&self, index: u64, tree_size: u64) -> Result<Vec<[u8; 32]>>;
+    async fn consistency(&self, first: u64, second: u64) -> Result<Vec<[u8; 32]>>;
+    /// Tree head over the current size, or `None` when the log has not grown since
+    /// the last recorded head.
+    async fn checkpoint(&self) -> Result<Option<TreeHead>>;
+    /// Records a tree head once it has been anchored.
+    async fn record(&self, head: &TreeHead) -> Result<()>;
+    /// Recorded head of the given size, or the latest one.
+    async fn head(&self, tree_size: Option<u64>) -> Result<Option<TreeHead>>;
 }
 
 pub trait UuidGenerator: Send + Sync {
@@ -32,6 +140,10 @@ pub trait UuidGenerator: Send + Sync {
 
 pub type DynSignerRepository = Arc<dyn SignerRepository>;
 pub type DynStorageRepository = Arc<dyn StorageRepository>;
+pub type DynBatchStatusRepository = Arc<dyn BatchStatusRepository>;
+pub type DynDeadLetterRepository = Arc<dyn DeadLetterRepository>;
+pub type DynChainHeadRepository = Arc<dyn ChainHeadRepository>;
+pub type DynTransparencyLog = Arc<dyn TransparencyLog>;
 pub type DynChannel<T> = Arc<dyn Channel<T>>;
 pub type DynHasher = Arc<dyn Hasher>;
 pub type DynUuidGenerator = Arc<dyn UuidGenerator>;
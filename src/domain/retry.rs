@@ -0,0 +1,39 @@
+use crate::domain::{Batch, Stage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    Retry { attempt: u32, delay: Duration },
+    Exhausted { attempts: u32 },
+}
+
+impl RetryPolicy {
+    /// Decides what to do after the `failures`-th consecutive failure of a batch,
+    /// doubling the delay on every retry up to `max_delay`.
+    pub fn decide(&self, failures: u32) -> RetryDecision {
+        if failures > self.max_attempts {
+            return RetryDecision::Exhausted { attempts: failures };
+        }
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        RetryDecision::Retry { attempt: failures, delay }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub stage: Stage,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+    pub batch: Batch,
+}
@@ -30,11 +30,26 @@ pub struct Condition {
     pub op: Operator,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sort {
+    pub field: String,
+    pub order: SortOrder,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Query {
     pub and: Option<Vec<Condition>>,
     pub or: Option<Vec<Condition>>,
     pub not: Option<Vec<Condition>>,
+    pub sort: Option<Sort>,
+    pub size: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
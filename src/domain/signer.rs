@@ -0,0 +1,70 @@
+use crate::domain::{HashAlgorithm, InclusionProof};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerIssueKind {
+    /// A record cannot be parsed.
+    Malformed,
+    /// Sequence numbers are skipped or repeated: records were removed or inserted.
+    OutOfSequence,
+    /// A record does not point at the hash of the record before it.
+    BrokenLink,
+    /// A record's content no longer matches its hash.
+    Rewritten,
+    /// A record's signature does not verify with the signer's public key.
+    BadSignature,
+    /// The records end before the last one the signer wrote.
+    Truncated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerIssue {
+    pub kind: SignerIssueKind,
+    pub sequence: u64,
+    pub detail: String,
+}
+
+/// Outcome of checking the records a signer keeps locally.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerAudit {
+    pub backend: String,
+    pub public_key: String,
+    pub records: u64,
+    /// Hash of the last record, to be noted by auditors so a later rollback of the
+    /// whole file can be detected.
+    pub head: Option<String>,
+    pub verified: bool,
+    pub issues: Vec<SignerIssue>,
+}
+
+/// Where a batch digest sits in the aggregate anchored on its behalf: `proof` leads
+/// from the batch leaf to the aggregate root, which is what the signer anchored.
+#[derive(Debug, Clone)]
+pub struct AggregateReceipt {
+    pub batch_id: String,
+    pub digest: [u8; 32],
+    pub aggregate_id: String,
+    pub proof: InclusionProof,
+    pub algorithm: HashAlgorithm,
+    pub anchored_at: DateTime<Utc>,
+}
+
+/// Where and when a batch digest was written on chain, for auditors to look the
+/// transaction up on their own node or block explorer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnchorReceipt {
+    pub batch_id: String,
+    pub chain_id: u64,
+    pub contract: String,
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub block_hash: String,
+    pub block_timestamp: DateTime<Utc>,
+    pub signer: String,
+    /// Digest written by the transaction, so it can be anchored again after a reorg.
+    /// Missing from receipts recorded before it was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// Sealing buffered documents into a batch, before it is handed off.
+    Worker,
+    Storage,
+    Signer,
+    /// Appending a handed-off batch to the transparency log.
+    Log,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Worker => write!(f, "worker"),
+            Stage::Storage => write!(f, "storage"),
+            Stage::Signer => write!(f, "signer"),
+            Stage::Log => write!(f, "log"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchState {
+    Pending,
+    Stored,
+    Anchored,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub enum BatchEvent {
+    Stored,
+    Anchored,
+    Failed { stage: Stage, error: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchStatus {
+    pub id: String,
+    pub state: BatchState,
+    pub created_at: DateTime<Utc>,
+    pub stored_at: Option<DateTime<Utc>>,
+    pub anchored_at: Option<DateTime<Utc>>,
+    /// Failed signer or storage operations, each of which is retried.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl BatchStatus {
+    pub fn new(id: String, created_at: DateTime<Utc>) -> Self {
+        Self { id, state: BatchState::Pending, created_at, stored_at: None, anchored_at: None, attempts: 0, last_error: None }
+    }
+
+    /// Moves the batch through its lifecycle. A batch is `Completed` once it is both
+    /// stored and anchored; a failure marks it `Failed` until a later attempt succeeds.
+    pub fn apply(&mut self, event: BatchEvent, at: DateTime<Utc>) {
+        match event {
+            BatchEvent::Stored => {
+                self.stored_at.get_or_insert(at);
+            }
+            BatchEvent::Anchored => {
+                self.anchored_at.get_or_insert(at);
+            }
+            BatchEvent::Failed { stage, error } => {
+                self.attempts += 1;
+                self.last_error = Some(format!("{}: {}", stage, error));
+                if self.state != BatchState::Completed {
+                    self.state = BatchState::Failed;
+                }
+                return;
+            }
+        }
+
+        self.state = match (self.stored_at, self.anchored_at) {
+            (Some(_), Some(_)) => BatchState::Completed,
+            (Some(_), None) => BatchState::Stored,
+            (None, Some(_)) => BatchState::Anchored,
+            (None, None) => BatchState::Pending,
+        };
+    }
+}
@@ -1,15 +1,35 @@
 use crate::{
-    domain::{DynHasher, DynUuidGenerator},
-    infra::helper::{Sha256HasherHelper, UuidV4GeneratorHelper},
+    config::AppConfig,
+    domain::{DynChainHeadRepository, DynHasher, DynUuidGenerator, HashSpec, RetryPolicy},
+    infra::{
+        chain::ChainLinker,
+        helper::{SpecHasherHelper, UuidV4GeneratorHelper},
+        retry::RetryTracker,
+    },
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-pub fn make_hasher() -> DynHasher {
-    let hasher = Sha256HasherHelper {};
-    Arc::new(hasher)
+pub fn make_hasher(config: &AppConfig) -> DynHasher {
+    let hasher = &config.hasher;
+    let spec = HashSpec { algorithm: hasher.algorithm, scheme: hasher.scheme, canonicalization: hasher.canonicalization };
+    Arc::new(SpecHasherHelper::new(spec))
 }
 
 pub fn make_uuid_generator() -> DynUuidGenerator {
     let uuid = UuidV4GeneratorHelper {};
     Arc::new(uuid)
 }
+
+pub fn make_retry_tracker(config: &AppConfig) -> RetryTracker {
+    let retry = &config.retry;
+    let policy = RetryPolicy {
+        max_attempts: retry.max_attempts,
+        base_delay: Duration::from_millis(retry.base_delay_ms),
+        max_delay: Duration::from_millis(retry.max_delay_ms),
+    };
+    RetryTracker::new(policy)
+}
+
+pub fn make_chain_linker(config: &AppConfig, heads: DynChainHeadRepository, hasher: DynHasher) -> ChainLinker {
+    ChainLinker::new(config.chain.stream.clone(), heads, hasher)
+}
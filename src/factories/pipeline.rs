@@ -1,10 +1,30 @@
-use crate::{domain::Pipeline, infra::channel::TokioChannel};
-use std::sync::Arc;
+use crate::{
+    config::{AppConfig, ChannelBackend},
+    domain::Pipeline,
+    infra::channel::{DiskChannel, TokioChannel},
+};
+use anyhow::Result;
+use std::{path::Path, sync::Arc};
 
-pub fn make_pipeline(size: usize) -> Pipeline {
-    let worker = TokioChannel::new(size);
-    let signer = TokioChannel::new(size);
-    let storage = TokioChannel::new(size);
+pub fn make_pipeline(config: &AppConfig) -> Result<Pipeline> {
+    let size = config.queue_size;
+    let channel = &config.channel;
 
-    Pipeline::new(Arc::new(worker), Arc::new(signer), Arc::new(storage))
+    let pipeline = match channel.backend {
+        ChannelBackend::Memory => {
+            let worker = TokioChannel::new(size);
+            let signer = TokioChannel::new(size);
+            let storage = TokioChannel::new(size);
+            Pipeline::new(Arc::new(worker), Arc::new(signer), Arc::new(storage))
+        }
+        ChannelBackend::Disk => {
+            let path = Path::new(&channel.path);
+            let worker = DiskChannel::open(path.join("worker"), size, channel.segment_size, channel.sync)?;
+            let signer = DiskChannel::open(path.join("signer"), size, channel.segment_size, channel.sync)?;
+            let storage = DiskChannel::open(path.join("storage"), size, channel.segment_size, channel.sync)?;
+            Pipeline::new(Arc::new(worker), Arc::new(signer), Arc::new(storage))
+        }
+    };
+
+    Ok(pipeline)
 }
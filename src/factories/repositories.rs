@@ -1,30 +1,145 @@
 use crate::{
-    config::AppConfig,
-    domain::{DynHasher, DynSignerRepository, DynStorageRepository},
-    infra::{signer::EthereumSignerRepository, storage::ElasticsearchStorageRepository},
+    config::{AppConfig, FeeStrategyKind, FinalityKind, SignerBackend, StorageBackend, TsaMode},
+    domain::{
+        DynBatchStatusRepository, DynChainHeadRepository, DynDeadLetterRepository, DynHasher, DynSignerRepository, DynStorageRepository,
+        DynTransparencyLog, HashSpec,
+    },
+    infra::{
+        chain::FileChainHeadRepository,
+        dead_letter::FileDeadLetterRepository,
+        helper::imprint_oid,
+        log::FileTransparencyLog,
+        prometheus::Prometheus,
+        signer::{
+            AggregatingSignerRepository, Confirmation, EthereumSignerRepository, FeePolicy, FeeStrategy, FileSignerRepository, Finality,
+            GasBudget, MemorySignerRepository, Rfc3161SignerRepository,
+        },
+        status::{ElasticsearchBatchStatusRepository, MemoryBatchStatusRepository},
+        storage::{ElasticsearchStorageRepository, MemoryStorageRepository},
+    },
 };
 use anyhow::Result;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-pub fn make_signer_repository(config: &AppConfig) -> Result<DynSignerRepository> {
-    let ethereum = &config.ethereum;
-    let signer = EthereumSignerRepository::new(
-        ethereum.url.clone(),
-        ethereum.contract.clone(),
-        ethereum.private_key.clone(),
-        ethereum.max_tx_pending,
-    )?;
-    Ok(Arc::new(signer))
+pub fn make_signer_repository(config: &AppConfig, prom: &Prometheus) -> Result<DynSignerRepository> {
+    let signer: DynSignerRepository = match config.signer.backend {
+        SignerBackend::Ethereum => {
+            let ethereum = &config.ethereum;
+            let strategy = match ethereum.fee_strategy {
+                FeeStrategyKind::Estimate => FeeStrategy::Estimate,
+                FeeStrategyKind::Percentile => {
+                    FeeStrategy::Percentile { percentile: ethereum.fee_percentile, blocks: ethereum.fee_history_blocks }
+                }
+                FeeStrategyKind::Fixed => FeeStrategy::Fixed { priority_fee: (ethereum.priority_fee_gwei * 1e9) as u128 },
+            };
+            let fees = FeePolicy {
+                strategy,
+                max_fee: (ethereum.max_fee_gwei * 1e9) as u128,
+                bump_percent: ethereum.fee_bump_percent,
+                replace_after: Duration::from_secs(ethereum.stuck_after_secs),
+            };
+            let limit = (ethereum.daily_budget_eth > 0.0).then_some((ethereum.daily_budget_eth * 1e18) as u128);
+            let finality = match ethereum.finality {
+                FinalityKind::Depth => Finality::Depth(ethereum.confirmations.max(1)),
+                FinalityKind::Finalized => Finality::Finalized,
+            };
+            let confirmation = Confirmation { finality, timeout: Duration::from_secs(ethereum.confirmation_timeout_secs) };
+            let budget = GasBudget::new(&ethereum.budget_path, limit, prom.clone())?;
+            Arc::new(EthereumSignerRepository::new(
+                ethereum.url.clone(),
+                ethereum.contract.clone(),
+                ethereum.deployment_block,
+                ethereum.private_key.clone(),
+                ethereum.max_tx_pending,
+                fees,
+                confirmation,
+                budget,
+                &ethereum.receipts_path,
+                &ethereum.sent_path,
+                prom.clone(),
+            )?)
+        }
+        SignerBackend::File => {
+            let file = &config.file_signer;
+            Arc::new(FileSignerRepository::new(&file.path, &file.key_path)?)
+        }
+        SignerBackend::Rfc3161 => {
+            let tsa = &config.tsa;
+            let algorithm = config.hasher.algorithm;
+            anyhow::ensure!(imprint_oid(algorithm).is_some(), "RFC 3161 timestamps need a sha256 or sha3-256 hasher, not {:?}", algorithm);
+            match tsa.mode {
+                TsaMode::Local => Arc::new(Rfc3161SignerRepository::local(&tsa.path, &tsa.certificate, &tsa.key, &tsa.policy)?),
+                TsaMode::Remote => Arc::new(Rfc3161SignerRepository::remote(
+                    &tsa.path,
+                    &tsa.certificate,
+                    tsa.url.clone(),
+                    Duration::from_millis(tsa.timeout_ms),
+                )?),
+            }
+        }
+        SignerBackend::Memory => Arc::new(MemorySignerRepository::new()),
+    };
+
+    let aggregator = &config.aggregator;
+    if !aggregator.enabled {
+        return Ok(signer);
+    }
+    Ok(Arc::new(AggregatingSignerRepository::new(
+        signer,
+        &aggregator.path,
+        config.hasher.algorithm,
+        Duration::from_millis(aggregator.window_ms),
+        aggregator.max_batches,
+    )?))
 }
 
 pub fn make_storage_repository(config: &AppConfig, hasher: DynHasher) -> Result<DynStorageRepository> {
-    let elastic = &config.elastic;
-    let storage = ElasticsearchStorageRepository::new(
-        elastic.url.clone(),
-        elastic.username.clone(),
-        elastic.password.clone(),
-        elastic.indices_pattern.clone(),
-        hasher,
-    )?;
-    Ok(Arc::new(storage))
+    let storage: DynStorageRepository = match config.storage.backend {
+        StorageBackend::Elastic => {
+            let elastic = &config.elastic;
+            Arc::new(ElasticsearchStorageRepository::new(
+                elastic.url.clone(),
+                elastic.username.clone(),
+                elastic.password.clone(),
+                elastic.indices_pattern.clone(),
+                hasher,
+            )?)
+        }
+        StorageBackend::Memory => Arc::new(MemoryStorageRepository::new(hasher)),
+    };
+    Ok(storage)
+}
+
+/// Batch statuses live next to the batches, in the storage backend.
+pub fn make_batch_status_repository(config: &AppConfig) -> Result<DynBatchStatusRepository> {
+    let status: DynBatchStatusRepository = match config.storage.backend {
+        StorageBackend::Elastic => {
+            let elastic = &config.elastic;
+            Arc::new(ElasticsearchBatchStatusRepository::new(
+                elastic.url.clone(),
+                elastic.username.clone(),
+                elastic.password.clone(),
+                elastic.status_index.clone(),
+            )?)
+        }
+        StorageBackend::Memory => Arc::new(MemoryBatchStatusRepository::new()),
+    };
+    Ok(status)
+}
+
+pub fn make_dead_letter_repository(config: &AppConfig) -> Result<DynDeadLetterRepository> {
+    let dead_letters = FileDeadLetterRepository::new(&config.retry.dead_letter_path)?;
+    Ok(Arc::new(dead_letters))
+}
+
+pub fn make_chain_head_repository(config: &AppConfig) -> Result<DynChainHeadRepository> {
+    let heads = FileChainHeadRepository::new(&config.chain.path)?;
+    Ok(Arc::new(heads))
+}
+
+pub fn make_transparency_log(config: &AppConfig) -> Result<DynTransparencyLog> {
+    let hasher = &config.hasher;
+    let spec = HashSpec { algorithm: hasher.algorithm, scheme: hasher.scheme, canonicalization: hasher.canonicalization };
+    let log = FileTransparencyLog::new(&config.transparency.path, spec)?;
+    Ok(Arc::new(log))
 }
@@ -0,0 +1,51 @@
+use crate::domain::{ChainHead, ChainHeadRepository};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Keeps the head of every stream in its own JSON file, replaced atomically on each
+/// batch, so a restarted instance continues the chain where it stopped.
+#[derive(Clone)]
+pub struct FileChainHeadRepository {
+    dir: PathBuf,
+}
+
+impl FileChainHeadRepository {
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).with_context(|| format!("failed to create chain directory {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, stream: &str) -> Result<PathBuf> {
+        let valid = !stream.is_empty() && stream.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        anyhow::ensure!(valid, "invalid chain stream name `{}`", stream);
+        Ok(self.dir.join(format!("{}.json", stream)))
+    }
+}
+
+#[async_trait]
+impl ChainHeadRepository for FileChainHeadRepository {
+    async fn load(&self, stream: &str) -> Result<Option<ChainHead>> {
+        let path = self.path(stream)?;
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes).with_context(|| format!("invalid chain head {}", path.display()))?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn save(&self, head: &ChainHead) -> Result<()> {
+        let path = self.path(&head.stream)?;
+        let tmp = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(&serde_json::to_vec(head)?).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
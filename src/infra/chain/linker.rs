@@ -0,0 +1,83 @@
+use crate::domain::{ChainHead, ChainLink, DynChainHeadRepository, DynHasher, HashSpec};
+use anyhow::Result;
+use chrono::Utc;
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+struct State {
+    loaded: bool,
+    head: Option<ChainHead>,
+}
+
+/// Appends batches to this instance's stream. Linking is serialized so every batch
+/// gets the next sequence number and its predecessor's anchored digest.
+#[derive(Clone)]
+pub struct ChainLinker {
+    stream: String,
+    heads: DynChainHeadRepository,
+    hasher: DynHasher,
+    state: Arc<Mutex<State>>,
+}
+
+impl ChainLinker {
+    pub fn new(stream: String, heads: DynChainHeadRepository, hasher: DynHasher) -> Self {
+        Self { stream, heads, hasher, state: Arc::new(Mutex::new(State { loaded: false, head: None })) }
+    }
+
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    /// Links a content digest after the current head. The stream stays locked until
+    /// the returned link is committed or dropped, so sequence numbers and timestamps
+    /// are handed out in order and a batch that is never handed off frees its sequence.
+    pub async fn link(&self, spec: &HashSpec, content: &[u8; 32]) -> Result<PendingLink> {
+        let mut state = self.state.clone().lock_owned().await;
+        if !state.loaded {
+            state.head = self.heads.load(&self.stream).await?;
+            state.loaded = true;
+        }
+
+        let link = ChainLink {
+            stream: self.stream.clone(),
+            sequence: state.head.as_ref().map_or(0, |head| head.sequence + 1),
+            previous: state.head.as_ref().map(|head| head.digest),
+            created_at: Utc::now(),
+        };
+        let digest = self.hasher.link(spec, content, &link);
+
+        Ok(PendingLink { state, heads: self.heads.clone(), link, digest })
+    }
+
+    pub async fn head(&self) -> Result<Option<ChainHead>> {
+        let state = self.state.lock().await;
+        if state.loaded {
+            return Ok(state.head.clone());
+        }
+        self.heads.load(&self.stream).await
+    }
+}
+
+/// A link that is not yet the head of its stream.
+pub struct PendingLink {
+    state: OwnedMutexGuard<State>,
+    heads: DynChainHeadRepository,
+    pub link: ChainLink,
+    /// Anchored digest of the linked batch.
+    pub digest: [u8; 32],
+}
+
+impl PendingLink {
+    /// Makes the link the head of its stream once its batch has been handed off. The
+    /// in-memory head moves even when persisting it fails, since the batch is out.
+    pub async fn commit(mut self) -> Result<()> {
+        let head = ChainHead {
+            stream: self.link.stream.clone(),
+            sequence: self.link.sequence,
+            digest: self.digest,
+            created_at: self.link.created_at,
+        };
+        self.state.head = Some(head.clone());
+        self.heads.save(&head).await
+    }
+}
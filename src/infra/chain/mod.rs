@@ -0,0 +1,5 @@
+mod file;
+mod linker;
+
+pub use file::*;
+pub use linker::*;
@@ -0,0 +1,371 @@
+use crate::domain::{Channel, ChannelError, Delivery};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task,
+    time::timeout,
+};
+use tracing::{error, info, warn};
+
+const SEGMENT_EXTENSION: &str = "log";
+const ACKS_FILE: &str = "acks";
+const ACKS_TMP_FILE: &str = "acks.tmp";
+const HEADER_LEN: usize = 16;
+
+type Record = (u64, Vec<u8>);
+
+/// A channel backed by a segmented write-ahead log on disk.
+///
+/// Every item is appended to the log before it is delivered. Items stay in the log
+/// until their offset is acknowledged, and unacknowledged items are delivered again
+/// when the channel is reopened after a restart or a crash.
+#[derive(Clone)]
+pub struct DiskChannel<T> {
+    log: Arc<StdMutex<Log>>,
+    sender: mpsc::Sender<Delivery<T>>,
+    receiver: Arc<Mutex<mpsc::Receiver<Delivery<T>>>>,
+    replay: Arc<StdMutex<VecDeque<Delivery<T>>>>,
+}
+
+impl<T: Serialize + DeserializeOwned> DiskChannel<T> {
+    pub fn open(dir: impl AsRef<Path>, buffer: usize, segment_size: u64, sync: bool) -> Result<Self> {
+        let dir = dir.as_ref();
+        let (mut log, records) = Log::open(dir, segment_size, sync)?;
+
+        let mut replay = VecDeque::new();
+        for (offset, payload) in records {
+            match serde_json::from_slice(&payload) {
+                Ok(item) => replay.push_back(Delivery { offset, item }),
+                Err(err) => {
+                    error!(?err, offset, dir = %dir.display(), "Dropping undecodable write-ahead log entry");
+                    log.ack(offset)?;
+                }
+            }
+        }
+        if !replay.is_empty() {
+            info!(count = replay.len(), dir = %dir.display(), "Replaying unacknowledged write-ahead log entries");
+        }
+
+        let (sender, receiver) = mpsc::channel(buffer);
+        Ok(Self {
+            log: Arc::new(StdMutex::new(log)),
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            replay: Arc::new(StdMutex::new(replay)),
+        })
+    }
+
+    /// Runs `f` on the log from the blocking pool, since appends and acks write and
+    /// sync files and would otherwise stall the runtime threads.
+    async fn with_log<R: Send + 'static>(&self, f: impl FnOnce(&mut Log) -> Result<R> + Send + 'static) -> Result<R> {
+        let log = self.log.clone();
+        task::spawn_blocking(move || {
+            let mut log = log.lock().map_err(|_| anyhow!("write-ahead log lock poisoned"))?;
+            f(&mut log)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl<T: Serialize + DeserializeOwned + Send + Sync> Channel<T> for DiskChannel<T> {
+    async fn send(&self, item: T) -> Result<()> {
+        let payload = serde_json::to_vec(&item)?;
+        let offset = self.with_log(move |log| log.append(&payload)).await?;
+        self.sender.send(Delivery { offset, item }).await.map_err(|_| anyhow!("channel closed"))
+    }
+
+    async fn send_timeout(&self, item: T, deadline: Duration) -> std::result::Result<(), ChannelError> {
+        let permit = match timeout(deadline, self.sender.reserve()).await {
+            Ok(Ok(permit)) => permit,
+            Ok(Err(_)) => return Err(ChannelError::Closed),
+            Err(_) => return Err(ChannelError::Full),
+        };
+        let payload = serde_json::to_vec(&item).map_err(anyhow::Error::from)?;
+        let offset = self.with_log(move |log| log.append(&payload)).await?;
+        permit.send(Delivery { offset, item });
+        Ok(())
+    }
+
+    async fn send_all_timeout(&self, items: Vec<T>, deadline: Duration) -> std::result::Result<(), ChannelError> {
+        let capacity = self.sender.max_capacity();
+        if items.len() > capacity {
+            return Err(ChannelError::TooLarge { capacity });
+        }
+        let permits = match timeout(deadline, self.sender.reserve_many(items.len())).await {
+            Ok(Ok(permits)) => permits,
+            Ok(Err(_)) => return Err(ChannelError::Closed),
+            Err(_) => return Err(ChannelError::Full),
+        };
+        let payloads = items.iter().map(serde_json::to_vec).collect::<serde_json::Result<Vec<_>>>().map_err(anyhow::Error::from)?;
+        // Every item is in the log before any is delivered, so a failed append leaves
+        // nothing half-enqueued.
+        let offsets = self.with_log(move |log| log.append_all(&payloads)).await?;
+        for ((permit, item), offset) in permits.zip(items).zip(offsets) {
+            permit.send(Delivery { offset, item });
+        }
+        Ok(())
+    }
+
+    async fn recv(&self) -> Option<Delivery<T>> {
+        if let Some(delivery) = self.replay.lock().ok().and_then(|mut replay| replay.pop_front()) {
+            return Some(delivery);
+        }
+        self.receiver.lock().await.recv().await
+    }
+
+    async fn ack(&self, offset: u64) -> Result<()> {
+        self.with_log(move |log| log.ack(offset)).await
+    }
+
+    fn pending(&self) -> usize {
+        let replay = self.replay.lock().map(|replay| replay.len()).unwrap_or_default();
+        replay + self.sender.max_capacity() - self.sender.capacity()
+    }
+}
+
+/// On-disk layout: one `<first offset>.log` file per segment and an `acks` file
+/// holding the acknowledged offsets of the live segments.
+///
+/// Each record is `len: u32 | crc32: u32 | offset: u64 | payload`, little endian,
+/// with the checksum covering the offset and the payload.
+struct Log {
+    dir: PathBuf,
+    segment_size: u64,
+    sync: bool,
+    active: File,
+    active_base: u64,
+    active_len: u64,
+    next_offset: u64,
+    segments: BTreeMap<u64, PathBuf>,
+    unacked: BTreeSet<u64>,
+    acks: File,
+}
+
+impl Log {
+    fn open(dir: &Path, segment_size: u64, sync: bool) -> Result<(Self, Vec<Record>)> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create write-ahead log directory {}", dir.display()))?;
+
+        let mut segments = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SEGMENT_EXTENSION) {
+                continue;
+            }
+            if let Some(base) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse::<u64>().ok()) {
+                segments.insert(base, path);
+            }
+        }
+
+        let mut records = Vec::new();
+        let mut next_offset = 0;
+        for (base, path) in &segments {
+            next_offset = next_offset.max(*base);
+            for (offset, payload) in read_segment(path)? {
+                next_offset = next_offset.max(offset + 1);
+                records.push((offset, payload));
+            }
+        }
+
+        let acked = read_acks(&dir.join(ACKS_FILE))?;
+        records.retain(|(offset, _)| !acked.contains(offset));
+        let unacked: BTreeSet<u64> = records.iter().map(|(offset, _)| *offset).collect();
+
+        let active_base = next_offset;
+        let active_path = segment_path(dir, active_base);
+        let active = OpenOptions::new().create(true).append(true).open(&active_path)?;
+        let active_len = active.metadata()?.len();
+        segments.insert(active_base, active_path);
+
+        let acks = OpenOptions::new().create(true).append(true).open(dir.join(ACKS_FILE))?;
+        let mut log =
+            Self { dir: dir.to_path_buf(), segment_size, sync, active, active_base, active_len, next_offset, segments, unacked, acks };
+
+        let bases: Vec<u64> = log.segments.keys().copied().collect();
+        for base in bases {
+            log.compact(base)?;
+        }
+        log.rewrite_acks()?;
+
+        Ok((log, records))
+    }
+
+    fn append(&mut self, payload: &[u8]) -> Result<u64> {
+        if self.active_len >= self.segment_size {
+            self.roll()?;
+        }
+
+        let offset = self.next_offset;
+        let len = u32::try_from(payload.len()).context("write-ahead log entry too large")?;
+        let mut record = Vec::with_capacity(HEADER_LEN + payload.len());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&checksum(offset, payload).to_le_bytes());
+        record.extend_from_slice(&offset.to_le_bytes());
+        record.extend_from_slice(payload);
+
+        self.active.write_all(&record)?;
+        if self.sync {
+            self.active.sync_data()?;
+        }
+
+        self.active_len += record.len() as u64;
+        self.next_offset += 1;
+        self.unacked.insert(offset);
+        Ok(offset)
+    }
+
+    /// Appends every payload or none: when one fails, those already appended are
+    /// acknowledged so they are not replayed either.
+    fn append_all(&mut self, payloads: &[Vec<u8>]) -> Result<Vec<u64>> {
+        let mut offsets = Vec::with_capacity(payloads.len());
+        for payload in payloads {
+            match self.append(payload) {
+                Ok(offset) => offsets.push(offset),
+                Err(err) => {
+                    for offset in offsets {
+                        self.ack(offset)?;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(offsets)
+    }
+
+    fn ack(&mut self, offset: u64) -> Result<()> {
+        if !self.unacked.remove(&offset) {
+            return Ok(());
+        }
+
+        self.acks.write_all(&offset.to_le_bytes())?;
+        if self.sync {
+            self.acks.sync_data()?;
+        }
+
+        if let Some((&base, _)) = self.segments.range(..=offset).next_back() {
+            if self.compact(base)? {
+                self.rewrite_acks()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn roll(&mut self) -> Result<()> {
+        let previous = self.active_base;
+        let path = segment_path(&self.dir, self.next_offset);
+        self.active = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.active_base = self.next_offset;
+        self.active_len = 0;
+        self.segments.insert(self.active_base, path);
+
+        if self.compact(previous)? {
+            self.rewrite_acks()?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the segment starting at `base` once it is sealed and fully acknowledged.
+    fn compact(&mut self, base: u64) -> Result<bool> {
+        if base == self.active_base {
+            return Ok(false);
+        }
+        let end = self.segments.range(base + 1..).next().map(|(next, _)| *next).unwrap_or(self.next_offset);
+        if self.unacked.range(base..end).next().is_some() {
+            return Ok(false);
+        }
+        if let Some(path) = self.segments.remove(&base) {
+            fs::remove_file(&path).with_context(|| format!("failed to remove write-ahead log segment {}", path.display()))?;
+        }
+        Ok(true)
+    }
+
+    /// Rewrites the acks file so it only holds offsets of segments that still exist.
+    fn rewrite_acks(&mut self) -> Result<()> {
+        let first = self.segments.keys().next().copied().unwrap_or(self.next_offset);
+        let tmp = self.dir.join(ACKS_TMP_FILE);
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            for offset in first..self.next_offset {
+                if !self.unacked.contains(&offset) {
+                    writer.write_all(&offset.to_le_bytes())?;
+                }
+            }
+            writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        }
+        fs::rename(&tmp, self.dir.join(ACKS_FILE))?;
+        self.acks = OpenOptions::new().append(true).open(self.dir.join(ACKS_FILE))?;
+        Ok(())
+    }
+}
+
+fn segment_path(dir: &Path, base: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", base, SEGMENT_EXTENSION))
+}
+
+fn checksum(offset: u64, payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&offset.to_le_bytes());
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+/// Reads every intact record of a segment, truncating a torn or corrupted tail.
+fn read_segment(path: &Path) -> Result<Vec<Record>> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut records = Vec::new();
+    let mut valid = 0u64;
+
+    loop {
+        let mut header = [0u8; HEADER_LEN];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+
+        let len = u32::from_le_bytes(header[0..4].try_into()?) as usize;
+        let crc = u32::from_le_bytes(header[4..8].try_into()?);
+        let offset = u64::from_le_bytes(header[8..16].try_into()?);
+
+        let mut payload = vec![0u8; len];
+        match reader.read_exact(&mut payload) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+        if checksum(offset, &payload) != crc {
+            break;
+        }
+
+        valid += (HEADER_LEN + len) as u64;
+        records.push((offset, payload));
+    }
+
+    let size = fs::metadata(path)?.len();
+    if valid < size {
+        warn!(path = %path.display(), valid, size, "Truncating damaged write-ahead log segment tail");
+        OpenOptions::new().write(true).open(path)?.set_len(valid)?;
+    }
+
+    Ok(records)
+}
+
+fn read_acks(path: &Path) -> Result<HashSet<u64>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(bytes.chunks_exact(8).filter_map(|chunk| chunk.try_into().ok()).map(u64::from_le_bytes).collect())
+}
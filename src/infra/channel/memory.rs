@@ -0,0 +1,76 @@
+use crate::domain::{Channel, ChannelError, Delivery};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::timeout,
+};
+
+#[derive(Clone)]
+pub struct TokioChannel<T> {
+    sender: mpsc::Sender<Delivery<T>>,
+    receiver: Arc<Mutex<mpsc::Receiver<Delivery<T>>>>,
+    offset: Arc<AtomicU64>,
+}
+
+impl<T> TokioChannel<T> {
+    pub fn new(buffer: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(buffer);
+        Self { sender, receiver: Arc::new(Mutex::new(receiver)), offset: Arc::new(AtomicU64::new(0)) }
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync> Channel<T> for TokioChannel<T> {
+    async fn send(&self, item: T) -> Result<()> {
+        let offset = self.offset.fetch_add(1, Ordering::SeqCst);
+        self.sender.send(Delivery { offset, item }).await.map_err(|_| anyhow!("channel closed"))
+    }
+
+    async fn send_timeout(&self, item: T, deadline: Duration) -> Result<(), ChannelError> {
+        let permit = match timeout(deadline, self.sender.reserve()).await {
+            Ok(Ok(permit)) => permit,
+            Ok(Err(_)) => return Err(ChannelError::Closed),
+            Err(_) => return Err(ChannelError::Full),
+        };
+        let offset = self.offset.fetch_add(1, Ordering::SeqCst);
+        permit.send(Delivery { offset, item });
+        Ok(())
+    }
+
+    async fn send_all_timeout(&self, items: Vec<T>, deadline: Duration) -> Result<(), ChannelError> {
+        let capacity = self.sender.max_capacity();
+        if items.len() > capacity {
+            return Err(ChannelError::TooLarge { capacity });
+        }
+        let permits = match timeout(deadline, self.sender.reserve_many(items.len())).await {
+            Ok(Ok(permits)) => permits,
+            Ok(Err(_)) => return Err(ChannelError::Closed),
+            Err(_) => return Err(ChannelError::Full),
+        };
+        for (permit, item) in permits.zip(items) {
+            let offset = self.offset.fetch_add(1, Ordering::SeqCst);
+            permit.send(Delivery { offset, item });
+        }
+        Ok(())
+    }
+
+    async fn recv(&self) -> Option<Delivery<T>> {
+        self.receiver.lock().await.recv().await
+    }
+
+    async fn ack(&self, _offset: u64) -> Result<()> {
+        Ok(())
+    }
+
+    fn pending(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
+    }
+}
@@ -1,3 +1,5 @@
-mod channel;
+mod disk;
+mod memory;
 
-pub use channel::*;
+pub use disk::*;
+pub use memory::*;
@@ -0,0 +1,72 @@
+use crate::domain::{DeadLetter, DeadLetterRepository, Stage};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+/// Stores every dead letter as its own JSON file, so failed batches survive restarts
+/// and do not depend on the storage backend that may be the one failing.
+#[derive(Clone)]
+pub struct FileDeadLetterRepository {
+    dir: PathBuf,
+}
+
+impl FileDeadLetterRepository {
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).with_context(|| format!("failed to create dead-letter directory {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, stage: Stage, id: &str) -> Option<PathBuf> {
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| self.dir.join(format!("{}-{}.json", stage, id)))
+    }
+}
+
+#[async_trait]
+impl DeadLetterRepository for FileDeadLetterRepository {
+    async fn put(&self, letter: &DeadLetter) -> Result<()> {
+        let path = self.path(letter.stage, &letter.batch.id).context("invalid batch id for dead letter")?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(letter)?).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<DeadLetter>> {
+        let mut letters = Vec::new();
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = fs::read(&path).await?;
+            letters.push(serde_json::from_slice(&bytes).with_context(|| format!("invalid dead letter {}", path.display()))?);
+        }
+        letters.sort_by_key(|letter: &DeadLetter| letter.failed_at);
+        Ok(letters)
+    }
+
+    async fn get(&self, stage: Stage, id: &str) -> Result<Option<DeadLetter>> {
+        let Some(path) = self.path(stage, id) else { return Ok(None) };
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn remove(&self, stage: Stage, id: &str) -> Result<bool> {
+        let Some(path) = self.path(stage, id) else { return Ok(false) };
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::domain::{Canonicalization, Document};
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Serializes a document into `out` exactly as it gets hashed.
+pub fn write_canonical<W: Write>(out: &mut W, doc: &Document, canonicalization: Canonicalization) -> Result<()> {
+    match canonicalization {
+        Canonicalization::Legacy => serde_json::to_writer(out, doc)?,
+        Canonicalization::Jcs => write_object(out, doc)?,
+    }
+    Ok(())
+}
+
+/// RFC 8785: members sorted by the UTF-16 code units of their names, no whitespace,
+/// numbers formatted as ECMAScript does, strings with minimal escaping.
+fn write_value<W: Write>(out: &mut W, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => out.write_all(b"null")?,
+        Value::Bool(b) => out.write_all(if *b { b"true" } else { b"false" })?,
+        Value::Number(n) => {
+            let n = n.as_f64().ok_or_else(|| anyhow::anyhow!("number {} is not representable as a double", n))?;
+            out.write_all(ryu_js::Buffer::new().format_finite(n).as_bytes())?;
+        }
+        Value::String(s) => serde_json::to_writer(&mut *out, s)?,
+        Value::Array(items) => {
+            out.write_all(b"[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write_value(out, item)?;
+            }
+            out.write_all(b"]")?;
+        }
+        Value::Object(map) => write_object(out, map)?,
+    }
+    Ok(())
+}
+
+fn write_object<W: Write>(out: &mut W, map: &Map<String, Value>) -> Result<()> {
+    let mut members: Vec<_> = map.iter().collect();
+    members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+    out.write_all(b"{")?;
+    for (i, (key, value)) in members.into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut *out, key)?;
+        out.write_all(b":")?;
+        write_value(out, value)?;
+    }
+    out.write_all(b"}")?;
+    Ok(())
+}
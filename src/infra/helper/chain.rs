@@ -0,0 +1,50 @@
+use crate::{
+    domain::{DigestStream, Document, HashSpec},
+    infra::helper::{write_canonical, HashState},
+};
+use anyhow::Result;
+
+/// Sequential hash chain: each document is hashed after the hex digest of everything
+/// before it, and the final hex digest is hashed once more.
+pub struct ChainStream {
+    spec: HashSpec,
+    partial: Option<[u8; 32]>,
+}
+
+impl ChainStream {
+    pub fn new(spec: HashSpec) -> Self {
+        Self { spec, partial: None }
+    }
+
+    /// Continues a chain from the partial digest of the documents already hashed.
+    pub fn resume(spec: HashSpec, partial: Option<[u8; 32]>) -> Self {
+        Self { spec, partial }
+    }
+
+    pub fn partial(&self) -> Option<[u8; 32]> {
+        self.partial
+    }
+
+    fn state_after_partial(&self) -> HashState {
+        let mut state = HashState::new(self.spec.algorithm);
+        if let Some(partial) = &self.partial {
+            let mut hex = [0u8; 64];
+            hex::encode_to_slice(partial, &mut hex).expect("64-byte buffer");
+            state.update(&hex);
+        }
+        state
+    }
+}
+
+impl DigestStream for ChainStream {
+    fn update(&mut self, doc: &Document) -> Result<()> {
+        let mut state = self.state_after_partial();
+        write_canonical(&mut state, doc, self.spec.canonicalization)?;
+        self.partial = Some(state.finalize());
+        Ok(())
+    }
+
+    fn finalize(self: Box<Self>) -> [u8; 32] {
+        self.state_after_partial().finalize()
+    }
+}
@@ -0,0 +1,64 @@
+use crate::domain::HashAlgorithm;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256, Sha3_256};
+use std::io::{self, Write};
+
+/// Incremental hash state for any supported algorithm. Implements `Write` so
+/// documents can be serialized straight into it.
+pub enum HashState {
+    Sha256(Sha256),
+    Sha3_256(Sha3_256),
+    Keccak256(Keccak256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HashState {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Sha3_256 => Self::Sha3_256(Sha3_256::new()),
+            HashAlgorithm::Keccak256 => Self::Keccak256(Keccak256::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::default()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha3_256(hasher) => hasher.update(data),
+            Self::Keccak256(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        match self {
+            Self::Sha256(hasher) => hasher.finalize().into(),
+            Self::Sha3_256(hasher) => hasher.finalize().into(),
+            Self::Keccak256(hasher) => hasher.finalize().into(),
+            Self::Blake3(hasher) => hasher.finalize().into(),
+        }
+    }
+}
+
+impl Write for HashState {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hashes the concatenation of `parts` with the given algorithm.
+pub fn hash(algorithm: HashAlgorithm, parts: &[&[u8]]) -> [u8; 32] {
+    let mut state = HashState::new(algorithm);
+    for part in parts {
+        state.update(part);
+    }
+    state.finalize()
+}
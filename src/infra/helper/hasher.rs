@@ -1,19 +1,74 @@
-use crate::domain::{Document, Hasher};
+use crate::{
+    domain::{ChainLink, ChainProof, DigestStream, Document, DocumentProof, HashScheme, HashSpec, Hasher},
+    infra::helper::{leaf, link_digest, root_from_path, ChainStream, MerkleStream, MerkleTree},
+};
 use anyhow::Result;
-use sha2::{Digest, Sha256};
 
+/// Hashes new batches with the configured spec and recomputes older batches with
+/// the spec they were recorded with.
 #[derive(Clone)]
-pub struct Sha256HasherHelper;
+pub struct SpecHasherHelper {
+    spec: HashSpec,
+}
+
+impl SpecHasherHelper {
+    pub fn new(spec: HashSpec) -> Self {
+        Self { spec }
+    }
+}
 
-impl Hasher for Sha256HasherHelper {
-    fn digest(&self, docs: &Vec<Document>) -> Result<[u8; 32]> {
-        let mut partial = String::new();
-        for doc in docs {
-            let json = serde_json::to_string(doc)?;
-            let combined = format!("{}{}", partial, json);
-            let hash = Sha256::digest(combined.as_bytes());
-            partial = format!("{:x}", hash);
+impl Hasher for SpecHasherHelper {
+    fn spec(&self) -> HashSpec {
+        self.spec
+    }
+
+    fn stream(&self, spec: &HashSpec) -> Box<dyn DigestStream> {
+        match spec.scheme {
+            HashScheme::Chain => Box::new(ChainStream::new(*spec)),
+            HashScheme::Merkle => Box::new(MerkleStream::new(*spec)),
         }
-        Ok(Sha256::digest(partial.as_bytes()).into())
+    }
+
+    fn proof(&self, docs: &[Document], index: usize, spec: &HashSpec) -> Result<Option<DocumentProof>> {
+        if index >= docs.len() {
+            return Ok(None);
+        }
+        match spec.scheme {
+            HashScheme::Chain => {
+                let mut stream = ChainStream::new(*spec);
+                for doc in &docs[..index] {
+                    stream.update(doc)?;
+                }
+                let previous = stream.partial();
+                for doc in &docs[index..] {
+                    stream.update(doc)?;
+                }
+                let digest = Box::new(stream).finalize();
+                Ok(Some(DocumentProof::Chain(ChainProof { previous, following: docs[index + 1..].to_vec(), digest })))
+            }
+            HashScheme::Merkle => Ok(MerkleTree::new(docs, spec)?.proof(index).map(DocumentProof::Merkle)),
+        }
+    }
+
+    fn verify(&self, doc: &Document, proof: &DocumentProof, spec: &HashSpec) -> Result<bool> {
+        match proof {
+            DocumentProof::Chain(proof) => {
+                let mut stream = Box::new(ChainStream::resume(*spec, proof.previous));
+                stream.update(doc)?;
+                for doc in &proof.following {
+                    stream.update(doc)?;
+                }
+                Ok(stream.finalize() == proof.digest)
+            }
+            DocumentProof::Merkle(proof) => {
+                let leaf = leaf(doc, spec)?;
+                let root = root_from_path(spec.algorithm, proof.index, proof.tree_size, leaf, &proof.path);
+                Ok(leaf == proof.leaf && root == Some(proof.root))
+            }
+        }
+    }
+
+    fn link(&self, spec: &HashSpec, content: &[u8; 32], link: &ChainLink) -> [u8; 32] {
+        link_digest(spec.algorithm, content, link)
     }
 }
@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind},
+    path::Path,
+};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Reads a JSON lines file, cutting off a last line left incomplete by an
+/// interrupted write so the next append starts on a fresh line.
+pub fn read_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let len = file.metadata()?.len();
+
+    let (mut reader, mut line, mut valid, mut records) = (BufReader::new(file), String::new(), 0, Vec::new());
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 || !line.ends_with('\n') {
+            break;
+        }
+        records.push(serde_json::from_str(&line).with_context(|| format!("invalid record in {}", path.display()))?);
+        valid += read as u64;
+    }
+
+    if valid < len {
+        OpenOptions::new().write(true).open(path)?.set_len(valid)?;
+    }
+    Ok(records)
+}
+
+pub async fn append_line<T: Serialize>(path: &Path, record: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path).await?;
+    file.write_all(&line).await?;
+    file.sync_data().await?;
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::{
+    domain::{ChainLink, HashAlgorithm},
+    infra::helper::hash,
+};
+
+const LINK_DOMAIN: &[u8] = b"audita-link-v1\0";
+
+/// Anchored digest of a chained batch: its content digest bound to the stream,
+/// sequence, creation time and the anchored digest of the previous batch.
+pub fn link_digest(algorithm: HashAlgorithm, content: &[u8; 32], link: &ChainLink) -> [u8; 32] {
+    let stream = link.stream.as_bytes();
+    let (flag, previous) = match &link.previous {
+        Some(previous) => ([1u8], *previous),
+        None => ([0u8], [0u8; 32]),
+    };
+    hash(
+        algorithm,
+        &[
+            LINK_DOMAIN,
+            &(stream.len() as u64).to_be_bytes(),
+            stream,
+            &link.sequence.to_be_bytes(),
+            &link.created_at.timestamp_micros().to_be_bytes(),
+            &flag,
+            &previous,
+            content,
+        ],
+    )
+}
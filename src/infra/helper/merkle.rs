@@ -0,0 +1,235 @@
+use crate::{
+    domain::{DigestStream, Document, HashAlgorithm, HashSpec, InclusionProof},
+    infra::helper::{hash, write_canonical, HashState},
+};
+use anyhow::Result;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Merkle tree following RFC 6962: leaves and interior nodes are hashed with distinct
+/// prefixes, and a tree of `n` leaves splits at the largest power of two below `n`.
+/// The root is the batch digest.
+pub struct MerkleTree {
+    algorithm: HashAlgorithm,
+    leaves: Vec<[u8; 32]>,
+}
+
+impl MerkleTree {
+    pub fn new(docs: &[Document], spec: &HashSpec) -> Result<Self> {
+        let leaves = docs.iter().map(|doc| leaf(doc, spec)).collect::<Result<Vec<_>>>()?;
+        Ok(Self { algorithm: spec.algorithm, leaves })
+    }
+
+    /// Tree over leaf hashes computed elsewhere.
+    pub fn from_leaves(algorithm: HashAlgorithm, leaves: Vec<[u8; 32]>) -> Self {
+        Self { algorithm, leaves }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        root(self.algorithm, &self.leaves)
+    }
+
+    pub fn proof(&self, index: usize) -> Option<InclusionProof> {
+        let leaf = *self.leaves.get(index)?;
+        Some(InclusionProof {
+            index,
+            tree_size: self.leaves.len(),
+            leaf,
+            path: path(self.algorithm, &self.leaves, index),
+            root: self.root(),
+        })
+    }
+}
+
+/// Builds a root incrementally, keeping only the roots of the perfect subtrees
+/// seen so far (at most one per height).
+#[derive(Clone)]
+pub struct RootBuilder {
+    algorithm: HashAlgorithm,
+    subtrees: Vec<(u32, [u8; 32])>,
+}
+
+impl RootBuilder {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self { algorithm, subtrees: Vec::new() }
+    }
+
+    /// Continues a tree whose perfect subtree roots are `subtrees`, highest first.
+    pub fn resume(algorithm: HashAlgorithm, subtrees: Vec<(u32, [u8; 32])>) -> Self {
+        Self { algorithm, subtrees }
+    }
+
+    pub fn push(&mut self, leaf: [u8; 32]) {
+        self.push_nodes(leaf, |_, _| {});
+    }
+
+    /// Pushes a leaf, passing the root of every perfect subtree it completes to
+    /// `visit` along with the height of that subtree.
+    pub fn push_nodes(&mut self, leaf: [u8; 32], mut visit: impl FnMut(u32, &[u8; 32])) {
+        let (mut height, mut hash) = (0, leaf);
+        while let Some(&(top, left)) = self.subtrees.last() {
+            if top != height {
+                break;
+            }
+            self.subtrees.pop();
+            hash = node(self.algorithm, &left, &hash);
+            height += 1;
+            visit(height, &hash);
+        }
+        self.subtrees.push((height, hash));
+    }
+
+    pub fn finish(mut self) -> [u8; 32] {
+        let Some((_, mut root)) = self.subtrees.pop() else { return hash(self.algorithm, &[]) };
+        while let Some((_, left)) = self.subtrees.pop() {
+            root = node(self.algorithm, &left, &root);
+        }
+        root
+    }
+}
+
+/// Batch digest of the `merkle` scheme, computed one document at a time.
+pub struct MerkleStream {
+    spec: HashSpec,
+    builder: RootBuilder,
+}
+
+impl MerkleStream {
+    pub fn new(spec: HashSpec) -> Self {
+        Self { spec, builder: RootBuilder::new(spec.algorithm) }
+    }
+}
+
+impl DigestStream for MerkleStream {
+    fn update(&mut self, doc: &Document) -> Result<()> {
+        self.builder.push(leaf(doc, &self.spec)?);
+        Ok(())
+    }
+
+    fn finalize(self: Box<Self>) -> [u8; 32] {
+        self.builder.finish()
+    }
+}
+
+/// Source of the roots of contiguous leaf ranges `[start, end)`, for trees too large
+/// to hold in memory.
+pub trait LeafRanges {
+    fn root(&mut self, start: u64, end: u64) -> Result<[u8; 32]>;
+}
+
+/// Audit path of the leaf at `index` in the tree made of the first `tree_size` leaves
+/// (RFC 6962, section 2.1.1).
+pub fn audit_path(ranges: &mut impl LeafRanges, index: u64, tree_size: u64) -> Result<Vec<[u8; 32]>> {
+    anyhow::ensure!(index < tree_size, "leaf {} is outside a tree of {} leaves", index, tree_size);
+    let (mut start, mut end, mut siblings) = (0, tree_size, Vec::new());
+    while end - start > 1 {
+        let mid = start + split(end - start);
+        if index < mid {
+            siblings.push(ranges.root(mid, end)?);
+            end = mid;
+        } else {
+            siblings.push(ranges.root(start, mid)?);
+            start = mid;
+        }
+    }
+    siblings.reverse();
+    Ok(siblings)
+}
+
+/// Proof that the tree of `first` leaves is a prefix of the tree of `second` leaves
+/// (RFC 6962, section 2.1.2).
+pub fn consistency_path(ranges: &mut impl LeafRanges, first: u64, second: u64) -> Result<Vec<[u8; 32]>> {
+    anyhow::ensure!(0 < first && first <= second, "no consistency proof from {} to {} leaves", first, second);
+    let (mut m, mut start, mut end, mut complete) = (first, 0, second, true);
+    let mut nodes = Vec::new();
+    while m != end - start {
+        let k = split(end - start);
+        if m <= k {
+            nodes.push(ranges.root(start + k, end)?);
+            end = start + k;
+        } else {
+            nodes.push(ranges.root(start, start + k)?);
+            start += k;
+            m -= k;
+            complete = false;
+        }
+    }
+    if !complete {
+        nodes.push(ranges.root(start, end)?);
+    }
+    nodes.reverse();
+    Ok(nodes)
+}
+
+pub fn leaf(doc: &Document, spec: &HashSpec) -> Result<[u8; 32]> {
+    let mut state = HashState::new(spec.algorithm);
+    state.update(&[LEAF_PREFIX]);
+    write_canonical(&mut state, doc, spec.canonicalization)?;
+    Ok(state.finalize())
+}
+
+/// Leaf of an anchoring aggregate, binding a batch digest to the batch id.
+pub fn aggregate_leaf(algorithm: HashAlgorithm, id: &str, digest: &[u8; 32]) -> [u8; 32] {
+    hash(algorithm, &[&[LEAF_PREFIX], &(id.len() as u64).to_be_bytes(), id.as_bytes(), digest])
+}
+
+pub fn node(algorithm: HashAlgorithm, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hash(algorithm, &[&[NODE_PREFIX], left, right])
+}
+
+/// Largest power of two smaller than `n`, for `n > 1`.
+pub fn split(n: u64) -> u64 {
+    1 << (u64::BITS - 1 - (n - 1).leading_zeros())
+}
+
+fn root(algorithm: HashAlgorithm, leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => hash(algorithm, &[]),
+        1 => leaves[0],
+        n => {
+            let k = split(n as u64) as usize;
+            node(algorithm, &root(algorithm, &leaves[..k]), &root(algorithm, &leaves[k..]))
+        }
+    }
+}
+
+fn path(algorithm: HashAlgorithm, leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+    let n = leaves.len();
+    if n <= 1 {
+        return Vec::new();
+    }
+    let k = split(n as u64) as usize;
+    let (mut path, sibling) = if index < k {
+        (path(algorithm, &leaves[..k], index), root(algorithm, &leaves[k..]))
+    } else {
+        (path(algorithm, &leaves[k..], index - k), root(algorithm, &leaves[..k]))
+    };
+    path.push(sibling);
+    path
+}
+
+/// Recomputes the root from a leaf and its audit path (RFC 9162, section 2.1.3.2).
+pub fn root_from_path(algorithm: HashAlgorithm, index: usize, tree_size: usize, leaf: [u8; 32], path: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if index >= tree_size {
+        return None;
+    }
+    let (mut fn_, mut sn, mut root) = (index, tree_size - 1, leaf);
+    for sibling in path {
+        if sn == 0 {
+            return None;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            root = node(algorithm, sibling, &root);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            root = node(algorithm, &root, sibling);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    (sn == 0).then_some(root)
+}
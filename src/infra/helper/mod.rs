@@ -1,5 +1,21 @@
+mod canonical;
+mod chain;
+mod digest;
 mod hasher;
+mod json_lines;
+mod link;
+mod merkle;
+mod timestamp;
+mod tree_head;
 mod uuid_generator;
 
+pub use canonical::*;
+pub use chain::*;
+pub use digest::*;
 pub use hasher::*;
+pub use json_lines::*;
+pub use link::*;
+pub use merkle::*;
+pub use timestamp::*;
+pub use tree_head::*;
 pub use uuid_generator::*;
@@ -0,0 +1,299 @@
+use crate::domain::HashAlgorithm;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use chrono::{DateTime, Utc};
+use cmpv2::status::{PkiStatus, PkiStatusInfo};
+use cms::{
+    cert::{CertificateChoices, IssuerAndSerialNumber},
+    content_info::{CmsVersion, ContentInfo},
+    signed_data::{CertificateSet, EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfo, SignerInfos},
+};
+use der::{
+    asn1::{GeneralizedTime, Int, ObjectIdentifier, OctetString, SetOfVec},
+    Any, Decode, DecodePem, Encode, Sequence, Tag, Tagged,
+};
+use p256::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rand::RngCore;
+use rsa::{pkcs1::DecodeRsaPrivateKey, pkcs1v15, RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use x509_cert::{
+    attr::Attribute,
+    ext::pkix::name::GeneralName,
+    spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+    Certificate,
+};
+use x509_tsp::{MessageImprint, TimeStampReq, TimeStampResp, TspVersion, TstInfo};
+
+const ID_SHA_256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_SHA3_256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.8");
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const ID_SIGNING_CERTIFICATE_V2: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.47");
+const ID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const SHA256_WITH_RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// ESSCertIDv2 (RFC 5035), naming the certificate a token was signed with.
+#[derive(Clone, Debug, Sequence)]
+struct EssCertIdV2 {
+    /// SHA-256 when absent.
+    #[asn1(optional = "true")]
+    hash_algorithm: Option<AlgorithmIdentifierOwned>,
+    cert_hash: OctetString,
+    #[asn1(optional = "true")]
+    issuer_serial: Option<Any>,
+}
+
+#[derive(Clone, Debug, Sequence)]
+struct SigningCertificateV2 {
+    certs: Vec<EssCertIdV2>,
+    #[asn1(optional = "true")]
+    policies: Option<Any>,
+}
+
+/// Object identifier timestamp authorities know `algorithm` by, if any.
+pub fn imprint_oid(algorithm: HashAlgorithm) -> Option<ObjectIdentifier> {
+    match algorithm {
+        HashAlgorithm::Sha256 => Some(ID_SHA_256),
+        HashAlgorithm::Sha3_256 => Some(ID_SHA3_256),
+        _ => None,
+    }
+}
+
+/// DER-encoded TimeStampReq for `digest`, along with the nonce it carries.
+pub fn timestamp_request(digest: &[u8; 32], algorithm: HashAlgorithm) -> Result<(Vec<u8>, Vec<u8>)> {
+    let oid = imprint_oid(algorithm).ok_or_else(|| anyhow!("{:?} digests cannot be timestamped", algorithm))?;
+    let nonce = positive_random(8);
+    let request = TimeStampReq {
+        version: TspVersion::V1,
+        message_imprint: MessageImprint {
+            hash_algorithm: AlgorithmIdentifierOwned { oid, parameters: None },
+            hashed_message: OctetString::new(digest.as_slice())?,
+        },
+        req_policy: None,
+        nonce: Some(Int::new(&nonce)?),
+        cert_req: true,
+        extensions: None,
+    };
+    Ok((request.to_der()?, nonce))
+}
+
+/// Reads the PEM certificate tokens are signed with.
+pub fn load_certificate(pem: &[u8]) -> Result<Certificate> {
+    Ok(Certificate::from_pem(pem)?)
+}
+
+/// Contents of a timestamp token that passed validation.
+#[derive(Debug, Clone)]
+pub struct Timestamp {
+    pub digest: [u8; 32],
+    pub time: DateTime<Utc>,
+    pub serial: String,
+    pub nonce: Option<Vec<u8>>,
+}
+
+/// Validates a DER TimeStampResp: the status must be granted, the token must be signed
+/// by `certificate` over signed attributes matching the TSTInfo, and the imprint must
+/// be a 32-byte hash.
+pub fn validate_response(response: &[u8], certificate: &Certificate) -> Result<Timestamp> {
+    let response = TimeStampResp::from_der(response).context("invalid TimeStampResp")?;
+    ensure!(
+        matches!(response.status.status, PkiStatus::Accepted | PkiStatus::GrantedWithMods),
+        "timestamp request was not granted: {:?}",
+        response.status.status
+    );
+    let token = response.time_stamp_token.context("granted response carries no token")?;
+    ensure!(token.content_type == ID_SIGNED_DATA, "token is not CMS SignedData");
+    let signed: SignedData = token.content.decode_as()?;
+
+    let encapsulated = &signed.encap_content_info;
+    ensure!(encapsulated.econtent_type == ID_CT_TST_INFO, "token does not encapsulate a TSTInfo");
+    let content = encapsulated.econtent.as_ref().context("token has no TSTInfo")?;
+    ensure!(content.tag() == Tag::OctetString, "TSTInfo is not wrapped in an OCTET STRING");
+    let info = TstInfo::from_der(content.value()).context("invalid TSTInfo")?;
+
+    let [signer] = signed.signer_infos.0.as_slice() else { bail!("token must have exactly one signer") };
+    ensure!(signer.digest_alg.oid == ID_SHA_256, "token is not signed over SHA-256");
+    let attributes = signer.signed_attrs.as_ref().context("token has no signed attributes")?;
+    let attribute = |oid| attributes.iter().find(|attribute| attribute.oid == oid).and_then(|attribute| attribute.values.get(0));
+
+    let content_type: ObjectIdentifier = attribute(ID_CONTENT_TYPE).context("token has no content type attribute")?.decode_as()?;
+    ensure!(content_type == ID_CT_TST_INFO, "content type attribute does not name TSTInfo");
+    let message_digest: OctetString = attribute(ID_MESSAGE_DIGEST).context("token has no message digest attribute")?.decode_as()?;
+    ensure!(message_digest.as_bytes() == Sha256::digest(content.value()).as_slice(), "TSTInfo does not match its signed digest");
+    if let Some(value) = attribute(ID_SIGNING_CERTIFICATE_V2) {
+        let signing: SigningCertificateV2 = value.decode_as()?;
+        let id = signing.certs.first().context("signing certificate attribute names no certificate")?;
+        if id.hash_algorithm.as_ref().is_none_or(|algorithm| algorithm.oid == ID_SHA_256) {
+            ensure!(id.cert_hash.as_bytes() == Sha256::digest(certificate.to_der()?).as_slice(), "token names another certificate");
+        }
+    }
+
+    let spki = &certificate.tbs_certificate.subject_public_key_info;
+    verify(spki, &signer.signature_algorithm, &attributes.to_der()?, signer.signature.as_bytes())?;
+
+    let digest = info.message_imprint.hashed_message.as_bytes().try_into().context("timestamped hash is not 32 bytes")?;
+    let time = DateTime::<Utc>::from(info.gen_time.to_system_time());
+    Ok(Timestamp {
+        digest,
+        time,
+        serial: hex::encode(info.serial_number.as_bytes()),
+        nonce: info.nonce.map(|nonce| nonce.as_bytes().to_vec()),
+    })
+}
+
+fn verify(spki: &SubjectPublicKeyInfoOwned, algorithm: &AlgorithmIdentifierOwned, message: &[u8], signature: &[u8]) -> Result<()> {
+    use rsa::signature::Verifier;
+
+    match spki.algorithm.oid {
+        ID_EC_PUBLIC_KEY => {
+            ensure!(algorithm.oid == ECDSA_WITH_SHA256, "unexpected signature algorithm {}", algorithm.oid);
+            let key = p256::ecdsa::VerifyingKey::from_public_key_der(&spki.to_der()?).context("certificate key is not P-256")?;
+            let signature = p256::ecdsa::DerSignature::from_bytes(signature)?;
+            key.verify(message, &signature).context("token signature does not verify")
+        }
+        RSA_ENCRYPTION => {
+            ensure!(
+                matches!(algorithm.oid, RSA_ENCRYPTION | SHA256_WITH_RSA_ENCRYPTION),
+                "unexpected signature algorithm {}",
+                algorithm.oid
+            );
+            let key = pkcs1v15::VerifyingKey::<Sha256>::new(RsaPublicKey::from_public_key_der(&spki.to_der()?)?);
+            key.verify(message, &pkcs1v15::Signature::try_from(signature)?).context("token signature does not verify")
+        }
+        oid => bail!("unsupported certificate key algorithm {}", oid),
+    }
+}
+
+enum SigningKey {
+    P256(p256::ecdsa::SigningKey),
+    Rsa(Box<pkcs1v15::SigningKey<Sha256>>),
+}
+
+/// Issues timestamp tokens itself, signing with a configured certificate and key, so
+/// anchoring works offline.
+pub struct LocalTimestampAuthority {
+    certificate: Certificate,
+    key: SigningKey,
+    policy: ObjectIdentifier,
+}
+
+impl LocalTimestampAuthority {
+    /// Pairs `certificate` with the PEM private key for it, PKCS#8 or the SEC1 / PKCS#1
+    /// forms written by `openssl ecparam` and `openssl genrsa`.
+    pub fn new(certificate: Certificate, key: &str, policy: &str) -> Result<Self> {
+        let spki = &certificate.tbs_certificate.subject_public_key_info;
+        let key = match spki.algorithm.oid {
+            ID_EC_PUBLIC_KEY => {
+                let secret = p256::SecretKey::from_pkcs8_pem(key).or_else(|_| p256::SecretKey::from_sec1_pem(key))?;
+                let public = p256::PublicKey::from_public_key_der(&spki.to_der()?)?;
+                ensure!(secret.public_key() == public, "timestamp key does not match the certificate");
+                SigningKey::P256(secret.into())
+            }
+            RSA_ENCRYPTION => {
+                let secret = RsaPrivateKey::from_pkcs8_pem(key).or_else(|_| RsaPrivateKey::from_pkcs1_pem(key))?;
+                ensure!(
+                    secret.to_public_key() == RsaPublicKey::from_public_key_der(&spki.to_der()?)?,
+                    "timestamp key does not match the certificate"
+                );
+                SigningKey::Rsa(Box::new(pkcs1v15::SigningKey::new(secret)))
+            }
+            oid => bail!("unsupported certificate key algorithm {}", oid),
+        };
+        let policy = policy.parse().map_err(|err| anyhow!("invalid timestamp policy {}: {}", policy, err))?;
+        Ok(Self { certificate, key, policy })
+    }
+
+    /// Answers a DER TimeStampReq with a granted DER TimeStampResp.
+    pub fn respond(&self, request: &[u8]) -> Result<Vec<u8>> {
+        use rsa::signature::{SignatureEncoding, Signer};
+
+        let request = TimeStampReq::from_der(request).context("invalid TimeStampReq")?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let info = TstInfo {
+            version: TspVersion::V1,
+            policy: request.req_policy.unwrap_or(self.policy),
+            message_imprint: request.message_imprint,
+            serial_number: Int::new(&positive_random(16))?,
+            gen_time: GeneralizedTime::from_unix_duration(Duration::from_secs(now.as_secs()))?,
+            accuracy: None,
+            ordering: false,
+            nonce: request.nonce,
+            tsa: Some(GeneralName::DirectoryName(self.certificate.tbs_certificate.subject.clone())),
+            extensions: None,
+        };
+        let content = info.to_der()?;
+
+        let signing_certificate = SigningCertificateV2 {
+            certs: vec![EssCertIdV2 {
+                hash_algorithm: None,
+                cert_hash: OctetString::new(Sha256::digest(self.certificate.to_der()?).as_slice())?,
+                issuer_serial: None,
+            }],
+            policies: None,
+        };
+        let attributes: SetOfVec<Attribute> = vec![
+            attribute(ID_CONTENT_TYPE, Any::encode_from(&ID_CT_TST_INFO)?)?,
+            attribute(ID_MESSAGE_DIGEST, Any::encode_from(&OctetString::new(Sha256::digest(&content).as_slice())?)?)?,
+            attribute(ID_SIGNING_CERTIFICATE_V2, Any::encode_from(&signing_certificate)?)?,
+        ]
+        .try_into()?;
+        let signed = attributes.to_der()?;
+        let (signature_algorithm, signature) = match &self.key {
+            SigningKey::P256(key) => {
+                let signature: p256::ecdsa::Signature = key.sign(&signed);
+                (ECDSA_WITH_SHA256, signature.to_der().to_vec())
+            }
+            SigningKey::Rsa(key) => (SHA256_WITH_RSA_ENCRYPTION, key.sign(&signed).to_vec()),
+        };
+
+        let signer = SignerInfo {
+            version: CmsVersion::V1,
+            sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: self.certificate.tbs_certificate.issuer.clone(),
+                serial_number: self.certificate.tbs_certificate.serial_number.clone(),
+            }),
+            digest_alg: AlgorithmIdentifierOwned { oid: ID_SHA_256, parameters: None },
+            signed_attrs: Some(attributes),
+            signature_algorithm: AlgorithmIdentifierOwned { oid: signature_algorithm, parameters: None },
+            signature: OctetString::new(signature)?,
+            unsigned_attrs: None,
+        };
+        let certificates = match request.cert_req {
+            true => Some(CertificateSet::try_from(vec![CertificateChoices::Certificate(self.certificate.clone())])?),
+            false => None,
+        };
+        let signed_data = SignedData {
+            version: CmsVersion::V3,
+            digest_algorithms: vec![AlgorithmIdentifierOwned { oid: ID_SHA_256, parameters: None }].try_into()?,
+            encap_content_info: EncapsulatedContentInfo {
+                econtent_type: ID_CT_TST_INFO,
+                econtent: Some(Any::new(Tag::OctetString, content)?),
+            },
+            certificates,
+            crls: None,
+            signer_infos: SignerInfos::try_from(vec![signer])?,
+        };
+
+        let response = TimeStampResp {
+            status: PkiStatusInfo { status: PkiStatus::Accepted, status_string: None, fail_info: None },
+            time_stamp_token: Some(ContentInfo { content_type: ID_SIGNED_DATA, content: Any::encode_from(&signed_data)? }),
+        };
+        Ok(response.to_der()?)
+    }
+}
+
+fn attribute(oid: ObjectIdentifier, value: Any) -> Result<Attribute> {
+    Ok(Attribute { oid, values: vec![value].try_into()? })
+}
+
+/// Random bytes read as a positive, minimally encoded INTEGER.
+fn positive_random(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes[0] = (bytes[0] & 0x7f) | 0x40;
+    bytes
+}
@@ -0,0 +1,10 @@
+use crate::{domain::HashAlgorithm, infra::helper::hash};
+use chrono::{DateTime, Utc};
+
+const TREE_HEAD_DOMAIN: &[u8] = b"audita-sth-v1\0";
+
+/// Anchored digest of a signed tree head: the log root bound to the tree size and
+/// the time the head was taken.
+pub fn tree_head_digest(algorithm: HashAlgorithm, tree_size: u64, root: &[u8; 32], timestamp: DateTime<Utc>) -> [u8; 32] {
+    hash(algorithm, &[TREE_HEAD_DOMAIN, &tree_size.to_be_bytes(), &timestamp.timestamp_micros().to_be_bytes(), root])
+}
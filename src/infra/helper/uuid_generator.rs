@@ -6,6 +6,6 @@ pub struct UuidV4GeneratorHelper;
 
 impl UuidGenerator for UuidV4GeneratorHelper {
     fn generate(&self) -> String {
-        return Uuid::new_v4().to_string();
+        Uuid::new_v4().to_string()
     }
 }
@@ -0,0 +1,333 @@
+use crate::{
+    domain::{Document, HashAlgorithm, HashScheme, HashSpec, LogEntry, TransparencyLog, TreeHead},
+    infra::helper::{append_line, audit_path, consistency_path, leaf, node, read_lines, split, tree_head_digest, LeafRanges, RootBuilder},
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    fs,
+    io::{AsyncSeekExt, AsyncWriteExt},
+    sync::Mutex,
+    task::spawn_blocking,
+};
+
+const LEAF_SIZE: u64 = 32;
+
+#[derive(Serialize, Deserialize)]
+struct EntryRecord {
+    batch_id: String,
+    start: u64,
+    count: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeadRecord {
+    tree_size: u64,
+    root: String,
+    timestamp: DateTime<Utc>,
+    digest: String,
+}
+
+struct State {
+    size: u64,
+    entries: HashMap<String, LogEntry>,
+    heads: Vec<TreeHead>,
+    /// Roots of the perfect subtrees covering the leaves, where the next append
+    /// continues the tree.
+    frontier: RootBuilder,
+}
+
+/// Keeps the transparency log in a directory: `leaves` holds the leaf hashes back to
+/// back, while `entries` and `heads` are JSON lines. An entry is written after its
+/// leaves, so leaves left behind by an interrupted append are dropped on startup.
+/// `nodes-<h>` holds the roots of the aligned perfect subtrees of `2^h` leaves the
+/// same way, so any subtree root, and with it every proof, takes O(log n) reads.
+#[derive(Clone)]
+pub struct FileTransparencyLog {
+    dir: PathBuf,
+    spec: HashSpec,
+    state: Arc<Mutex<State>>,
+}
+
+impl FileTransparencyLog {
+    /// Opens the log in `dir`, creating it with `spec` when it does not exist yet. An
+    /// existing log keeps hashing with the spec it was created with.
+    pub fn new(dir: impl AsRef<Path>, spec: HashSpec) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).with_context(|| format!("failed to create log directory {}", dir.display()))?;
+
+        let meta = dir.join("spec.json");
+        let spec = match std::fs::read(&meta) {
+            Ok(bytes) => serde_json::from_slice(&bytes).with_context(|| format!("invalid log spec {}", meta.display()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let spec = HashSpec { scheme: HashScheme::Merkle, ..spec };
+                std::fs::write(&meta, serde_json::to_vec(&spec)?)?;
+                spec
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let entries = read_lines::<EntryRecord>(&dir.join("entries"))?;
+        let size = entries.last().map_or(0, |entry| entry.start + entry.count);
+        let leaves = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join("leaves"))?;
+        let stored = leaves.metadata()?.len() / LEAF_SIZE;
+        anyhow::ensure!(stored >= size, "log entries cover {} leaves but only {} are stored", size, stored);
+        leaves.set_len(size * LEAF_SIZE)?;
+        let frontier = nodes(&dir, spec.algorithm, size)?;
+
+        let heads = read_lines::<HeadRecord>(&dir.join("heads"))?
+            .into_iter()
+            .map(|record| {
+                Ok(TreeHead {
+                    tree_size: record.tree_size,
+                    root: decode(&record.root)?,
+                    timestamp: record.timestamp,
+                    digest: decode(&record.digest)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let entries = entries
+            .into_iter()
+            .map(|record| (record.batch_id.clone(), LogEntry { batch_id: record.batch_id, start: record.start, count: record.count }))
+            .collect();
+
+        Ok(Self { dir, spec, state: Arc::new(Mutex::new(State { size, entries, heads, frontier })) })
+    }
+
+    /// Runs `read` against the leaves on a blocking thread, once the log is known to
+    /// hold at least `tree_size` of them.
+    async fn read<T, F>(&self, tree_size: u64, read: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut FileRanges) -> Result<T> + Send + 'static,
+    {
+        let size = self.state.lock().await.size;
+        anyhow::ensure!(tree_size <= size, "the log holds {} leaves, not {}", size, tree_size);
+        let mut ranges = FileRanges { dir: self.dir.clone(), algorithm: self.spec.algorithm, levels: HashMap::new() };
+        spawn_blocking(move || read(&mut ranges)).await?
+    }
+}
+
+#[async_trait]
+impl TransparencyLog for FileTransparencyLog {
+    fn spec(&self) -> HashSpec {
+        self.spec
+    }
+
+    async fn append(&self, batch_id: &str, docs: &[Document]) -> Result<LogEntry> {
+        let leaves = docs.iter().map(|doc| leaf(doc, &self.spec)).collect::<Result<Vec<_>>>()?;
+
+        let mut state = self.state.lock().await;
+        if let Some(entry) = state.entries.get(batch_id) {
+            return Ok(entry.clone());
+        }
+
+        // levels[h] holds the hashes appended at height h, leaves included.
+        let entry = LogEntry { batch_id: batch_id.to_string(), start: state.size, count: docs.len() as u64 };
+        let mut frontier = state.frontier.clone();
+        let mut levels = vec![Vec::with_capacity(leaves.len() * LEAF_SIZE as usize)];
+        for leaf in leaves {
+            levels[0].extend_from_slice(&leaf);
+            frontier.push_nodes(leaf, |height, hash| {
+                levels.resize(levels.len().max(height as usize + 1), Vec::new());
+                levels[height as usize].extend_from_slice(hash);
+            });
+        }
+        for (height, bytes) in levels.iter().enumerate() {
+            write_at(&level(&self.dir, height as u32), entry.start >> height, bytes).await?;
+        }
+
+        let record = EntryRecord { batch_id: entry.batch_id.clone(), start: entry.start, count: entry.count };
+        append_line(&self.dir.join("entries"), &record).await?;
+
+        state.size += entry.count;
+        state.frontier = frontier;
+        state.entries.insert(entry.batch_id.clone(), entry.clone());
+        Ok(entry)
+    }
+
+    async fn size(&self) -> Result<u64> {
+        Ok(self.state.lock().await.size)
+    }
+
+    async fn entry(&self, batch_id: &str) -> Result<Option<LogEntry>> {
+        Ok(self.state.lock().await.entries.get(batch_id).cloned())
+    }
+
+    async fn leaf(&self, index: u64) -> Result<Option<[u8; 32]>> {
+        if index >= self.size().await? {
+            return Ok(None);
+        }
+        self.read(index + 1, move |ranges| ranges.root(index, index + 1)).await.map(Some)
+    }
+
+    async fn root(&self, tree_size: u64) -> Result<[u8; 32]> {
+        self.read(tree_size, move |ranges| ranges.root(0, tree_size)).await
+    }
+
+    async fn inclusion(&self, index: u64, tree_size: u64) -> Result<Vec<[u8; 32]>> {
+        self.read(tree_size, move |ranges| audit_path(ranges, index, tree_size)).await
+    }
+
+    async fn consistency(&self, first: u64, second: u64) -> Result<Vec<[u8; 32]>> {
+        self.read(second, move |ranges| consistency_path(ranges, first, second)).await
+    }
+
+    async fn checkpoint(&self) -> Result<Option<TreeHead>> {
+        let (size, last) = {
+            let state = self.state.lock().await;
+            (state.size, state.heads.last().map_or(0, |head| head.tree_size))
+        };
+        if size <= last {
+            return Ok(None);
+        }
+
+        let root = self.root(size).await?;
+        let timestamp = Utc::now().trunc_subsecs(6);
+        let digest = tree_head_digest(self.spec.algorithm, size, &root, timestamp);
+        Ok(Some(TreeHead { tree_size: size, root, timestamp, digest }))
+    }
+
+    async fn record(&self, head: &TreeHead) -> Result<()> {
+        let mut state = self.state.lock().await;
+        if let Some(last) = state.heads.last() {
+            anyhow::ensure!(head.tree_size > last.tree_size, "tree head {} does not extend head {}", head.tree_size, last.tree_size);
+        }
+
+        let record = HeadRecord {
+            tree_size: head.tree_size,
+            root: hex::encode(head.root),
+            timestamp: head.timestamp,
+            digest: hex::encode(head.digest),
+        };
+        append_line(&self.dir.join("heads"), &record).await?;
+        state.heads.push(head.clone());
+        Ok(())
+    }
+
+    async fn head(&self, tree_size: Option<u64>) -> Result<Option<TreeHead>> {
+        let state = self.state.lock().await;
+        let head = match tree_size {
+            Some(tree_size) => state.heads.binary_search_by_key(&tree_size, |head| head.tree_size).ok().map(|i| &state.heads[i]),
+            None => state.heads.last(),
+        };
+        Ok(head.cloned())
+    }
+}
+
+/// Computes subtree roots from the stored roots of the perfect subtrees they split into.
+struct FileRanges {
+    dir: PathBuf,
+    algorithm: HashAlgorithm,
+    levels: HashMap<u32, File>,
+}
+
+impl FileRanges {
+    fn node(&mut self, height: u32, index: u64) -> Result<[u8; 32]> {
+        if !self.levels.contains_key(&height) {
+            self.levels.insert(height, File::open(level(&self.dir, height))?);
+        }
+        let file = &mut self.levels.get_mut(&height).expect("level was just opened");
+        file.seek(SeekFrom::Start(index * LEAF_SIZE))?;
+        let mut hash = [0u8; 32];
+        file.read_exact(&mut hash)?;
+        Ok(hash)
+    }
+}
+
+impl LeafRanges for FileRanges {
+    fn root(&mut self, start: u64, end: u64) -> Result<[u8; 32]> {
+        let size = end - start;
+        if size == 0 {
+            return Ok(RootBuilder::new(self.algorithm).finish());
+        }
+        if size.is_power_of_two() && start.is_multiple_of(size) {
+            return self.node(size.trailing_zeros(), start / size);
+        }
+        let mid = start + split(size);
+        Ok(node(self.algorithm, &self.root(start, mid)?, &self.root(mid, end)?))
+    }
+}
+
+/// File holding the subtree roots of `2^height` leaves; height 0 holds the leaves.
+fn level(dir: &Path, height: u32) -> PathBuf {
+    match height {
+        0 => dir.join("leaves"),
+        height => dir.join(format!("nodes-{}", height)),
+    }
+}
+
+/// Writes `bytes` at hash `index` of a level file, dropping whatever a failed earlier
+/// write left past it.
+async fn write_at(path: &Path, index: u64, bytes: &[u8]) -> Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(path).await?;
+    file.set_len(index * LEAF_SIZE).await?;
+    file.seek(SeekFrom::Start(index * LEAF_SIZE)).await?;
+    file.write_all(bytes).await?;
+    file.sync_data().await?;
+    Ok(())
+}
+
+/// Trims the level files to the first `size` leaves and returns the frontier of the
+/// tree they hold. Levels that fall short, as in logs written before they existed,
+/// are rebuilt from the leaves.
+fn nodes(dir: &Path, algorithm: HashAlgorithm, size: u64) -> Result<RootBuilder> {
+    let mut complete = true;
+    for height in 1..u64::BITS {
+        let path = level(dir, height);
+        let expected = (size >> height) * LEAF_SIZE;
+        match OpenOptions::new().write(true).open(&path) {
+            Ok(file) if file.metadata()?.len() >= expected => file.set_len(expected)?,
+            Ok(_) => complete = false,
+            Err(err) if err.kind() == ErrorKind::NotFound => complete &= expected == 0,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    if complete {
+        let mut ranges = FileRanges { dir: dir.to_path_buf(), algorithm, levels: HashMap::new() };
+        let subtrees = (0..u64::BITS)
+            .rev()
+            .filter(|height| (size >> height) & 1 == 1)
+            .map(|height| Ok((height, ranges.node(height, (size >> height) - 1)?)))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(RootBuilder::resume(algorithm, subtrees));
+    }
+
+    let mut reader = BufReader::new(File::open(level(dir, 0))?);
+    let mut writers: Vec<BufWriter<File>> = Vec::new();
+    let mut frontier = RootBuilder::new(algorithm);
+    let mut leaf = [0u8; 32];
+    for _ in 0..size {
+        reader.read_exact(&mut leaf)?;
+        let mut completed = Vec::new();
+        frontier.push_nodes(leaf, |height, hash| completed.push((height, *hash)));
+        for (height, hash) in completed {
+            while writers.len() < height as usize {
+                let file = OpenOptions::new().create(true).truncate(true).write(true).open(level(dir, writers.len() as u32 + 1))?;
+                writers.push(BufWriter::new(file));
+            }
+            writers[height as usize - 1].write_all(&hash)?;
+        }
+    }
+    for writer in writers {
+        writer.into_inner().map_err(|err| err.into_error())?.sync_data()?;
+    }
+    Ok(frontier)
+}
+
+fn decode(encoded: &str) -> Result<[u8; 32]> {
+    let mut digest = [0u8; 32];
+    hex::decode_to_slice(encoded, &mut digest)?;
+    Ok(digest)
+}
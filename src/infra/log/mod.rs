@@ -0,0 +1,3 @@
+mod file;
+
+pub use file::*;
@@ -1,5 +1,10 @@
+pub mod chain;
 pub mod channel;
+pub mod dead_letter;
 pub mod helper;
+pub mod log;
 pub mod prometheus;
+pub mod retry;
 pub mod signer;
+pub mod status;
 pub mod storage;
@@ -1,4 +1,4 @@
-use prometheus::{Counter, Encoder, Gauge, Histogram, HistogramOpts, Registry, TextEncoder};
+use prometheus::{Counter, CounterVec, Encoder, Gauge, Histogram, HistogramOpts, Opts, Registry, TextEncoder};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -6,16 +6,35 @@ pub struct Prometheus {
     pub registry: Registry,
 
     pub docs_total: Arc<Counter>,
+    pub docs_rejected_total: Arc<Counter>,
     pub batches_total: Arc<Counter>,
+    pub batches_sealed_by_size_total: Arc<Counter>,
+    pub batches_sealed_by_timeout_total: Arc<Counter>,
 
     pub batches_error_total: Arc<Counter>,
     pub storage_errors_total: Arc<Counter>,
     pub signer_errors_total: Arc<Counter>,
+    pub retries_total: Arc<Counter>,
+    pub dead_letters_total: Arc<Counter>,
+    pub log_heads_total: Arc<Counter>,
+    pub signer_nonce_resyncs_total: Arc<Counter>,
+    pub signer_nonce_gaps_total: Arc<Counter>,
+    pub signer_tx_replacements_total: Arc<Counter>,
+    pub signer_reverts_total: Arc<Counter>,
+    pub signer_reorgs_total: Arc<Counter>,
+
+    pub syslog_messages_total: Arc<CounterVec>,
+    pub syslog_parse_errors_total: Arc<CounterVec>,
+    pub syslog_dropped_total: Arc<CounterVec>,
 
     pub worker_queue_size: Arc<Gauge>,
     pub storage_queue_size: Arc<Gauge>,
     pub signer_queue_size: Arc<Gauge>,
     pub batch_size: Arc<Gauge>,
+    pub log_tree_size: Arc<Gauge>,
+    pub signer_nonces_in_flight: Arc<Gauge>,
+    pub signer_gas_spent_wei: Arc<Gauge>,
+    pub signer_gas_budget_exhausted: Arc<Gauge>,
 
     pub batch_processing_latency: Arc<Histogram>,
     pub storage_request_latency: Arc<Histogram>,
@@ -27,15 +46,55 @@ impl Prometheus {
         let registry = Registry::new();
 
         let docs_total = Counter::new("app_docs_total", "Total number of documents processed").unwrap();
+        let docs_rejected_total =
+            Counter::new("app_docs_rejected_total", "Total number of documents rejected because the worker queue was full").unwrap();
         let batches_total = Counter::new("app_batches_total", "Total number of batches processed").unwrap();
+        let batches_sealed_by_size_total =
+            Counter::new("app_batches_sealed_by_size_total", "Total number of batches sealed because they reached the batch size").unwrap();
+        let batches_sealed_by_timeout_total =
+            Counter::new("app_batches_sealed_by_timeout_total", "Total number of batches sealed because the maximum batch wait elapsed")
+                .unwrap();
         let batches_error_total = Counter::new("app_batches_error_total", "Total number of batch processing errors").unwrap();
         let storage_errors_total = Counter::new("app_storage_errors_total", "Total number of storage errors").unwrap();
         let signer_errors_total = Counter::new("app_signer_errors_total", "Total number of signer errors").unwrap();
+        let retries_total = Counter::new("app_retries_total", "Total number of scheduled signer and storage retries").unwrap();
+        let dead_letters_total = Counter::new("app_dead_letters_total", "Total number of batches moved to the dead-letter store").unwrap();
+        let log_heads_total = Counter::new("app_log_heads_total", "Total number of transparency log tree heads anchored").unwrap();
+        let signer_nonce_resyncs_total =
+            Counter::new("app_signer_nonce_resyncs_total", "Total number of signer nonce resyncs with the chain").unwrap();
+        let signer_nonce_gaps_total =
+            Counter::new("app_signer_nonce_gaps_total", "Total number of signer nonce gaps found and closed").unwrap();
+        let signer_tx_replacements_total =
+            Counter::new("app_signer_tx_replacements_total", "Total number of stuck signer transactions replaced with higher fees")
+                .unwrap();
+        let signer_reverts_total = Counter::new("app_signer_reverts_total", "Total number of signer transactions that reverted").unwrap();
+        let signer_reorgs_total =
+            Counter::new("app_signer_reorgs_total", "Total number of signer transactions taken out of their block by a reorg").unwrap();
+
+        let syslog_messages_total =
+            CounterVec::new(Opts::new("app_syslog_messages_total", "Total number of syslog messages received"), &["listener"]).unwrap();
+        let syslog_parse_errors_total = CounterVec::new(
+            Opts::new("app_syslog_parse_errors_total", "Total number of syslog messages whose header could not be fully parsed"),
+            &["listener"],
+        )
+        .unwrap();
+        let syslog_dropped_total = CounterVec::new(
+            Opts::new("app_syslog_dropped_total", "Total number of syslog messages dropped because the worker queue was unavailable"),
+            &["listener"],
+        )
+        .unwrap();
 
         let worker_queue_size = Gauge::new("app_worker_queue_size", "Current size of the worker queue").unwrap();
         let storage_queue_size = Gauge::new("app_storage_queue_size", "Current size of the storage queue").unwrap();
         let signer_queue_size = Gauge::new("app_signer_queue_size", "Current size of the signer queue").unwrap();
         let batch_size = Gauge::new("app_batch_size", "Size of the last processed batch").unwrap();
+        let log_tree_size = Gauge::new("app_log_tree_size", "Number of leaves in the transparency log").unwrap();
+        let signer_nonces_in_flight =
+            Gauge::new("app_signer_nonces_in_flight", "Number of signer nonces whose transaction is not mined yet").unwrap();
+        let signer_gas_spent_wei =
+            Gauge::new("app_signer_gas_spent_wei", "Fees in wei paid by the signer account in the current UTC day").unwrap();
+        let signer_gas_budget_exhausted =
+            Gauge::new("app_signer_gas_budget_exhausted", "1 while anchoring is paused because the daily gas budget is exhausted").unwrap();
 
         let latency_buckets = vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
 
@@ -57,15 +116,34 @@ impl Prometheus {
         .unwrap();
 
         registry.register(Box::new(docs_total.clone())).unwrap();
+        registry.register(Box::new(docs_rejected_total.clone())).unwrap();
         registry.register(Box::new(batches_total.clone())).unwrap();
+        registry.register(Box::new(batches_sealed_by_size_total.clone())).unwrap();
+        registry.register(Box::new(batches_sealed_by_timeout_total.clone())).unwrap();
         registry.register(Box::new(batches_error_total.clone())).unwrap();
         registry.register(Box::new(storage_errors_total.clone())).unwrap();
         registry.register(Box::new(signer_errors_total.clone())).unwrap();
+        registry.register(Box::new(retries_total.clone())).unwrap();
+        registry.register(Box::new(dead_letters_total.clone())).unwrap();
+        registry.register(Box::new(log_heads_total.clone())).unwrap();
+        registry.register(Box::new(signer_nonce_resyncs_total.clone())).unwrap();
+        registry.register(Box::new(signer_nonce_gaps_total.clone())).unwrap();
+        registry.register(Box::new(signer_tx_replacements_total.clone())).unwrap();
+        registry.register(Box::new(signer_reverts_total.clone())).unwrap();
+        registry.register(Box::new(signer_reorgs_total.clone())).unwrap();
+
+        registry.register(Box::new(syslog_messages_total.clone())).unwrap();
+        registry.register(Box::new(syslog_parse_errors_total.clone())).unwrap();
+        registry.register(Box::new(syslog_dropped_total.clone())).unwrap();
 
         registry.register(Box::new(worker_queue_size.clone())).unwrap();
         registry.register(Box::new(storage_queue_size.clone())).unwrap();
         registry.register(Box::new(signer_queue_size.clone())).unwrap();
         registry.register(Box::new(batch_size.clone())).unwrap();
+        registry.register(Box::new(log_tree_size.clone())).unwrap();
+        registry.register(Box::new(signer_nonces_in_flight.clone())).unwrap();
+        registry.register(Box::new(signer_gas_spent_wei.clone())).unwrap();
+        registry.register(Box::new(signer_gas_budget_exhausted.clone())).unwrap();
 
         registry.register(Box::new(batch_processing_latency.clone())).unwrap();
         registry.register(Box::new(storage_request_latency.clone())).unwrap();
@@ -75,16 +153,35 @@ impl Prometheus {
             registry,
 
             docs_total: Arc::new(docs_total),
+            docs_rejected_total: Arc::new(docs_rejected_total),
             batches_total: Arc::new(batches_total),
+            batches_sealed_by_size_total: Arc::new(batches_sealed_by_size_total),
+            batches_sealed_by_timeout_total: Arc::new(batches_sealed_by_timeout_total),
 
             batches_error_total: Arc::new(batches_error_total),
             storage_errors_total: Arc::new(storage_errors_total),
             signer_errors_total: Arc::new(signer_errors_total),
+            retries_total: Arc::new(retries_total),
+            dead_letters_total: Arc::new(dead_letters_total),
+            log_heads_total: Arc::new(log_heads_total),
+            signer_nonce_resyncs_total: Arc::new(signer_nonce_resyncs_total),
+            signer_nonce_gaps_total: Arc::new(signer_nonce_gaps_total),
+            signer_tx_replacements_total: Arc::new(signer_tx_replacements_total),
+            signer_reverts_total: Arc::new(signer_reverts_total),
+            signer_reorgs_total: Arc::new(signer_reorgs_total),
+
+            syslog_messages_total: Arc::new(syslog_messages_total),
+            syslog_parse_errors_total: Arc::new(syslog_parse_errors_total),
+            syslog_dropped_total: Arc::new(syslog_dropped_total),
 
             worker_queue_size: Arc::new(worker_queue_size),
             storage_queue_size: Arc::new(storage_queue_size),
             signer_queue_size: Arc::new(signer_queue_size),
             batch_size: Arc::new(batch_size),
+            log_tree_size: Arc::new(log_tree_size),
+            signer_nonces_in_flight: Arc::new(signer_nonces_in_flight),
+            signer_gas_spent_wei: Arc::new(signer_gas_spent_wei),
+            signer_gas_budget_exhausted: Arc::new(signer_gas_budget_exhausted),
 
             batch_processing_latency: Arc::new(batch_processing_latency),
             storage_request_latency: Arc::new(storage_request_latency),
@@ -0,0 +1,35 @@
+use crate::domain::{RetryDecision, RetryPolicy, Stage};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Counts consecutive failures of each batch per stage and applies the retry policy.
+#[derive(Clone)]
+pub struct RetryTracker {
+    policy: RetryPolicy,
+    failures: Arc<Mutex<HashMap<(Stage, String), u32>>>,
+}
+
+impl RetryTracker {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy, failures: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    pub fn failed(&self, stage: Stage, id: &str) -> RetryDecision {
+        let mut failures = self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let count = failures.entry((stage, id.to_string())).or_default();
+        *count += 1;
+
+        let decision = self.policy.decide(*count);
+        if let RetryDecision::Exhausted { .. } = decision {
+            failures.remove(&(stage, id.to_string()));
+        }
+        decision
+    }
+
+    pub fn reset(&self, stage: Stage, id: &str) {
+        let mut failures = self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        failures.remove(&(stage, id.to_string()));
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{
+    domain::{
+        AggregateReceipt, AnchorReceipt, Batch, DynSignerRepository, HashAlgorithm, HashScheme, HashSpec, SignerAudit, SignerRepository,
+    },
+    infra::helper::{aggregate_leaf, append_line, read_lines, MerkleTree},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{oneshot, Mutex, RwLock},
+    time::sleep,
+};
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// One anchored aggregate. Its root is recomputed from the listed batches, in order.
+#[derive(Clone, Serialize, Deserialize)]
+struct AggregateRecord {
+    id: String,
+    algorithm: HashAlgorithm,
+    anchored_at: DateTime<Utc>,
+    batches: Vec<AggregatedBatch>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct AggregatedBatch {
+    id: String,
+    digest: String,
+}
+
+struct Aggregate {
+    id: String,
+    algorithm: HashAlgorithm,
+    anchored_at: DateTime<Utc>,
+    batches: Vec<(String, [u8; 32])>,
+}
+
+impl Aggregate {
+    fn tree(&self) -> MerkleTree {
+        let leaves = self.batches.iter().map(|(id, digest)| aggregate_leaf(self.algorithm, id, digest)).collect();
+        MerkleTree::from_leaves(self.algorithm, leaves)
+    }
+}
+
+#[derive(Default)]
+struct Receipts {
+    aggregates: Vec<Arc<Aggregate>>,
+    /// Batch id to its aggregate and position in it.
+    index: HashMap<String, (usize, usize)>,
+}
+
+impl Receipts {
+    fn insert(&mut self, aggregate: Aggregate) {
+        let position = self.aggregates.len();
+        for (i, (id, _)) in aggregate.batches.iter().enumerate() {
+            // A batch is only aggregated again if its receipt was lost, so the first one stands.
+            self.index.entry(id.clone()).or_insert((position, i));
+        }
+        self.aggregates.push(Arc::new(aggregate));
+    }
+}
+
+struct Waiting {
+    id: String,
+    digest: [u8; 32],
+    done: oneshot::Sender<Result<(), String>>,
+}
+
+#[derive(Default)]
+struct Window {
+    generation: u64,
+    waiting: Vec<Waiting>,
+}
+
+impl Window {
+    fn take(&mut self) -> Vec<Waiting> {
+        self.generation += 1;
+        mem::take(&mut self.waiting)
+    }
+}
+
+/// Collects batch digests for a window and anchors only the Merkle root over them
+/// through the wrapped signer, as a batch of its own named `agg-<uuid>`. `publish`
+/// returns once the root is anchored and the receipts are written, so a failed
+/// aggregate is retried batch by batch like any failed publish. Batches anchored
+/// before aggregation was enabled are still resolved through the wrapped signer.
+#[derive(Clone)]
+pub struct AggregatingSignerRepository {
+    inner: DynSignerRepository,
+    path: PathBuf,
+    algorithm: HashAlgorithm,
+    window: Duration,
+    max_batches: usize,
+    open: Arc<Mutex<Window>>,
+    receipts: Arc<RwLock<Receipts>>,
+}
+
+impl AggregatingSignerRepository {
+    /// Wraps `inner`, keeping anchored aggregates in `<dir>/aggregates`. New
+    /// aggregates are hashed with `algorithm`; existing ones keep theirs.
+    pub fn new(
+        inner: DynSignerRepository, dir: impl AsRef<Path>, algorithm: HashAlgorithm, window: Duration, max_batches: usize,
+    ) -> Result<Self> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create aggregator directory {}", dir.display()))?;
+        let path = dir.join("aggregates");
+
+        let mut receipts = Receipts::default();
+        for record in read_lines::<AggregateRecord>(&path)? {
+            let batches = record
+                .batches
+                .into_iter()
+                .map(|batch| Ok((batch.id, decode(&batch.digest).with_context(|| format!("invalid digest in aggregate {}", record.id))?)))
+                .collect::<Result<Vec<_>>>()?;
+            let aggregate = Aggregate { id: record.id, algorithm: record.algorithm, anchored_at: record.anchored_at, batches };
+            receipts.insert(aggregate);
+        }
+        info!(aggregates = receipts.aggregates.len(), batches = receipts.index.len(), "Anchoring aggregator ready");
+
+        Ok(Self {
+            inner,
+            path,
+            algorithm,
+            window,
+            max_batches: max_batches.max(1),
+            open: Arc::new(Mutex::new(Window::default())),
+            receipts: Arc::new(RwLock::new(receipts)),
+        })
+    }
+
+    async fn aggregate_of(&self, id: &str) -> Option<(Arc<Aggregate>, usize)> {
+        let receipts = self.receipts.read().await;
+        let &(aggregate, position) = receipts.index.get(id)?;
+        Some((receipts.aggregates[aggregate].clone(), position))
+    }
+
+    /// Anchors the root over `waiting` and reports the outcome to every batch in it.
+    async fn anchor(self, waiting: Vec<Waiting>) {
+        let aggregate = Aggregate {
+            id: format!("agg-{}", Uuid::new_v4()),
+            algorithm: self.algorithm,
+            anchored_at: Utc::now().trunc_subsecs(6),
+            batches: waiting.iter().map(|waiting| (waiting.id.clone(), waiting.digest)).collect(),
+        };
+        let outcome = self.persist(aggregate).await.map_err(|err| {
+            error!(?err, batches = waiting.len(), "Failed to anchor aggregate");
+            format!("{:#}", err)
+        });
+        for waiting in waiting {
+            let _ = waiting.done.send(outcome.clone());
+        }
+    }
+
+    async fn persist(&self, aggregate: Aggregate) -> Result<()> {
+        let spec = HashSpec { algorithm: aggregate.algorithm, scheme: HashScheme::Merkle, ..HashSpec::default() };
+        let root = aggregate.tree().root();
+        let batch = Batch { id: aggregate.id.clone(), documents: Vec::new(), digest: root, hash: spec, link: None };
+        self.inner.publish(&batch).await?;
+
+        let record = AggregateRecord {
+            id: aggregate.id.clone(),
+            algorithm: aggregate.algorithm,
+            anchored_at: aggregate.anchored_at,
+            batches: aggregate.batches.iter().map(|(id, digest)| AggregatedBatch { id: id.clone(), digest: hex::encode(digest) }).collect(),
+        };
+        let mut receipts = self.receipts.write().await;
+        append_line(&self.path, &record).await?;
+        info!(aggregate_id = %aggregate.id, batches = aggregate.batches.len(), root = %hex::encode(root), "Anchored aggregate");
+        receipts.insert(aggregate);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SignerRepository for AggregatingSignerRepository {
+    async fn publish(&self, batch: &Batch) -> Result<()> {
+        if let Some((aggregate, position)) = self.aggregate_of(&batch.id).await {
+            if aggregate.batches[position].1 == batch.digest {
+                return Ok(());
+            }
+            bail!("batch {} is already aggregated with a different digest", batch.id);
+        }
+
+        let (done, outcome) = oneshot::channel();
+        let full = {
+            let mut open = self.open.lock().await;
+            if open.waiting.iter().any(|waiting| waiting.id == batch.id) {
+                bail!("batch {} is already waiting for an aggregate", batch.id);
+            }
+            if open.waiting.is_empty() {
+                let (repository, generation) = (self.clone(), open.generation);
+                tokio::spawn(async move {
+                    sleep(repository.window).await;
+                    let waiting = {
+                        let mut open = repository.open.lock().await;
+                        (open.generation == generation).then(|| open.take())
+                    };
+                    if let Some(waiting) = waiting {
+                        repository.anchor(waiting).await;
+                    }
+                });
+            }
+            open.waiting.push(Waiting { id: batch.id.clone(), digest: batch.digest, done });
+            (open.waiting.len() >= self.max_batches).then(|| open.take())
+        };
+        if let Some(waiting) = full {
+            tokio::spawn(self.clone().anchor(waiting));
+        }
+
+        outcome.await.map_err(|_| anyhow!("aggregate of batch {} was dropped", batch.id))?.map_err(|err| anyhow!(err))
+    }
+
+    /// The batch digest, once the aggregate root it leads to is the one anchored.
+    async fn digest(&self, id: &str) -> Result<Option<[u8; 32]>> {
+        let Some(receipt) = self.receipt(id).await? else { return self.inner.digest(id).await };
+        match self.inner.digest(&receipt.aggregate_id).await? {
+            Some(anchored) if anchored == receipt.proof.root => Ok(Some(receipt.digest)),
+            Some(_) => bail!("aggregate {} anchors another root than the receipt of batch {}", receipt.aggregate_id, id),
+            None => Ok(None),
+        }
+    }
+
+    async fn audit(&self) -> Result<Option<SignerAudit>> {
+        self.inner.audit().await
+    }
+
+    async fn token(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        match self.aggregate_of(id).await {
+            Some((aggregate, _)) => self.inner.token(&aggregate.id).await,
+            None => self.inner.token(id).await,
+        }
+    }
+
+    async fn receipt(&self, id: &str) -> Result<Option<AggregateReceipt>> {
+        let Some((aggregate, position)) = self.aggregate_of(id).await else { return Ok(None) };
+        let proof = aggregate.tree().proof(position).context("aggregate is missing the batch")?;
+        Ok(Some(AggregateReceipt {
+            batch_id: id.to_string(),
+            digest: aggregate.batches[position].1,
+            aggregate_id: aggregate.id.clone(),
+            proof,
+            algorithm: aggregate.algorithm,
+            anchored_at: aggregate.anchored_at,
+        }))
+    }
+
+    /// The transaction of the batch's aggregate.
+    async fn anchor(&self, id: &str) -> Result<Option<AnchorReceipt>> {
+        match self.aggregate_of(id).await {
+            Some((aggregate, _)) => self.inner.anchor(&aggregate.id).await,
+            None => self.inner.anchor(id).await,
+        }
+    }
+
+    fn concurrency(&self) -> usize {
+        self.max_batches
+    }
+}
+
+fn decode(encoded: &str) -> Result<[u8; 32]> {
+    let mut digest = [0u8; 32];
+    hex::decode_to_slice(encoded, &mut digest)?;
+    Ok(digest)
+}
@@ -0,0 +1,120 @@
+use crate::infra::prometheus::Prometheus;
+use anyhow::{ensure, Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex, time::sleep};
+use tracing::{info, warn};
+
+/// How often a paused signer checks whether the budget has room again.
+const PAUSE_CHECK: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize)]
+struct Spent {
+    day: NaiveDate,
+    wei: u128,
+}
+
+struct State {
+    spent: Spent,
+    /// Worst case of the transactions sent and not charged yet.
+    reserved: u128,
+    exhausted: bool,
+}
+
+/// Daily spend of the signer account on transaction fees. A transaction books its
+/// worst case, gas limit times maximum fee, before it is sent and is charged what it
+/// actually paid once mined. The spend of the current UTC day is kept in a file, so
+/// a restart does not reset the budget.
+pub struct GasBudget {
+    path: PathBuf,
+    limit: Option<u128>,
+    prom: Prometheus,
+    state: Mutex<State>,
+}
+
+impl GasBudget {
+    /// Keeps the spend in `path`. Without a `limit` the spend is only tracked.
+    pub fn new(path: impl AsRef<Path>, limit: Option<u128>, prom: Prometheus) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("failed to create gas budget directory {}", dir.display()))?;
+        }
+        let today = Utc::now().date_naive();
+        let spent = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).with_context(|| format!("invalid gas budget {}", path.display()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => Spent { day: today, wei: 0 },
+            Err(err) => return Err(err.into()),
+        };
+        let spent = if spent.day == today { spent } else { Spent { day: today, wei: 0 } };
+        prom.signer_gas_spent_wei.set(spent.wei as f64);
+        info!(spent_wei = %spent.wei, limit_wei = ?limit, "Gas budget ready");
+
+        Ok(Self { path, limit, prom, state: Mutex::new(State { spent, reserved: 0, exhausted: false }) })
+    }
+
+    /// Books `wei` for a transaction about to be sent. While the budget of the day
+    /// cannot cover it, anchoring pauses here until it can.
+    pub async fn reserve(&self, wei: u128) -> Result<()> {
+        if let Some(limit) = self.limit {
+            ensure!(wei <= limit, "a transaction may cost up to {} wei, more than the daily gas budget of {} wei", wei, limit);
+        }
+        while !self.try_reserve(wei).await {
+            sleep(PAUSE_CHECK).await;
+        }
+        Ok(())
+    }
+
+    /// Books `wei` if the budget of the day still covers it.
+    pub async fn try_reserve(&self, wei: u128) -> bool {
+        let mut state = self.state.lock().await;
+        self.roll(&mut state);
+        let fits = self.limit.is_none_or(|limit| state.spent.wei + state.reserved + wei <= limit);
+        if fits {
+            state.reserved += wei;
+        }
+        if fits == state.exhausted {
+            state.exhausted = !fits;
+            self.prom.signer_gas_budget_exhausted.set(if fits { 0.0 } else { 1.0 });
+            match fits {
+                true => info!("Gas budget has room again, resuming anchoring"),
+                false => warn!(spent_wei = %state.spent.wei, limit_wei = ?self.limit, "Daily gas budget exhausted, pausing anchoring"),
+            }
+        }
+        fits
+    }
+
+    /// Gives back a booking whose transactions were never mined.
+    pub async fn release(&self, reserved: u128) {
+        let mut state = self.state.lock().await;
+        state.reserved = state.reserved.saturating_sub(reserved);
+    }
+
+    /// Replaces a booking with the `wei` actually paid.
+    pub async fn charge(&self, reserved: u128, wei: u128) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.reserved = state.reserved.saturating_sub(reserved);
+        self.roll(&mut state);
+        state.spent.wei += wei;
+        self.prom.signer_gas_spent_wei.set(state.spent.wei as f64);
+
+        let tmp = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(&serde_json::to_vec(&state.spent)?).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+
+    fn roll(&self, state: &mut State) {
+        let today = Utc::now().date_naive();
+        if state.spent.day != today {
+            state.spent = Spent { day: today, wei: 0 };
+            self.prom.signer_gas_spent_wei.set(0.0);
+        }
+    }
+}
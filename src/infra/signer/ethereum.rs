@@ -1,30 +1,90 @@
-use crate::domain::{Batch, SignerRepository};
+use super::{nonce::NonceManager, FeePolicy, GasBudget};
+use crate::{
+    domain::{AnchorReceipt, Batch, HashSpec, SignerRepository},
+    infra::{
+        helper::{append_line, read_lines},
+        prometheus::Prometheus,
+    },
+};
 use alloy::{
-    network::{EthereumWallet, TransactionBuilder},
-    primitives::U256,
-    providers::{DynProvider, Provider, ProviderBuilder},
-    rpc::types::TransactionRequest,
+    eips::{BlockId, BlockNumberOrTag},
+    network::EthereumWallet,
+    primitives::{keccak256, Address, TxHash},
+    providers::{utils::Eip1559Estimation, DynProvider, Provider, ProviderBuilder},
+    rpc::types::TransactionReceipt,
     signers::local::PrivateKeySigner,
     sol,
+    sol_types::decode_revert_reason,
 };
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
 use std::{
-    sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
-    },
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
-use tokio::{runtime::Handle, sync::Semaphore, task, time::sleep};
+use tokio::{
+    runtime::Handle,
+    sync::{RwLock, Semaphore},
+    task,
+    time::{sleep, Instant},
+};
+use tracing::{debug, error, info, warn};
+
+/// How often the gas booked for a transaction given up on is checked for settlement.
+const SETTLE_CHECK: Duration = Duration::from_secs(15);
+/// Blocks searched per `eth_getLogs` call, within the range hosted nodes accept.
+const LOG_RANGE: u64 = 10_000;
+
+/// Transaction sent for a batch, recorded before it is waited on.
+#[derive(Serialize, Deserialize)]
+struct SentRecord {
+    batch_id: String,
+    tx_hash: String,
+}
 
+/// When a mined transaction counts as confirmed.
+#[derive(Clone, Copy, Debug)]
+pub enum Finality {
+    /// Once its block is this many blocks deep, counting the block itself.
+    Depth(u64),
+    /// Once its block is at or below the `finalized` block.
+    Finalized,
+}
+
+/// How the signer waits for a transaction: until `finality`, for at most `timeout`
+/// from the first send.
+#[derive(Clone, Copy, Debug)]
+pub struct Confirmation {
+    pub finality: Finality,
+    pub timeout: Duration,
+}
+
+/// Anchors batch digests with the `Auditability` contract. The transaction of every
+/// batch is recorded in a JSON lines file, so when and where a batch was anchored
+/// can be shown later.
 #[derive(Clone)]
 pub struct EthereumSignerRepository {
     provider: DynProvider,
-    signer: PrivateKeySigner,
+    address: Address,
+    chain_id: u64,
+    deployment_block: u64,
     instance: Auditability::AuditabilityInstance<(), DynProvider>,
-    nonce: Arc<AtomicU64>,
+    nonces: Arc<NonceManager>,
+    concurrency: usize,
     max_tx_pending: Arc<Semaphore>,
+    fees: FeePolicy,
+    confirmation: Confirmation,
+    budget: Arc<GasBudget>,
+    receipts_path: PathBuf,
+    receipts: Arc<RwLock<HashMap<String, AnchorReceipt>>>,
+    sent_path: PathBuf,
+    /// Transactions sent for batches without a recorded receipt yet.
+    sent: Arc<RwLock<HashMap<String, Vec<TxHash>>>>,
+    prom: Prometheus,
 }
 
 sol! {
@@ -34,65 +94,367 @@ sol! {
         function proof(string id, bytes32 digest) external view returns (bool);
         function hash(string id) external view returns (bytes32);
         function exists(string id) external view returns (bool);
+        event IndexStored(string indexed index, bytes32 hash);
     }
 }
 
 impl EthereumSignerRepository {
-    pub fn new(url: String, contract: String, pk: String, max_tx_pending: usize) -> Result<Self> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        url: String, contract: String, deployment_block: u64, pk: String, max_tx_pending: usize, fees: FeePolicy,
+        confirmation: Confirmation, budget: GasBudget, receipts_path: impl AsRef<Path>, sent_path: impl AsRef<Path>, prom: Prometheus,
+    ) -> Result<Self> {
         let signer: PrivateKeySigner = pk.parse()?;
-        let wallet = EthereumWallet::from(signer.clone());
+        let address = signer.address();
+        let wallet = EthereumWallet::from(signer);
         let url = url.parse()?;
         let provider = ProviderBuilder::new().wallet(wallet).on_http(url);
         let provider = DynProvider::new(provider);
         let contract = contract.parse()?;
         let instance = Auditability::new(contract, provider.clone());
-        let address = signer.address();
 
-        let nonce = task::block_in_place(|| Handle::current().block_on(async { provider.get_transaction_count(address).await }))?;
+        let (nonces, chain_id) = task::block_in_place(|| {
+            Handle::current().block_on(async {
+                let nonces = NonceManager::new(provider.clone(), address, prom.clone()).await?;
+                anyhow::Ok((nonces, provider.get_chain_id().await?))
+            })
+        })?;
+
+        let receipts_path = receipts_path.as_ref().to_path_buf();
+        if let Some(dir) = receipts_path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("failed to create receipts directory {}", dir.display()))?;
+        }
+        let receipts: HashMap<_, _> =
+            read_lines::<AnchorReceipt>(&receipts_path)?.into_iter().map(|receipt| (receipt.batch_id.clone(), receipt)).collect();
+        let sent_path = sent_path.as_ref().to_path_buf();
+        if let Some(dir) = sent_path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("failed to create sent transactions directory {}", dir.display()))?;
+        }
+        let mut sent: HashMap<String, Vec<TxHash>> = HashMap::new();
+        for record in read_lines::<SentRecord>(&sent_path)? {
+            if !receipts.contains_key(&record.batch_id) {
+                sent.entry(record.batch_id).or_default().push(record.tx_hash.parse()?);
+            }
+        }
+        info!(%address, chain_id, receipts = receipts.len(), pending = sent.len(), "Ethereum signer ready");
 
         Ok(Self {
             provider,
-            signer,
+            address,
+            chain_id,
+            deployment_block,
             instance,
-            nonce: Arc::new(AtomicU64::new(nonce)),
+            nonces: Arc::new(nonces),
+            concurrency: max_tx_pending,
             max_tx_pending: Arc::new(Semaphore::new(max_tx_pending)),
+            fees,
+            confirmation,
+            budget: Arc::new(budget),
+            receipts_path,
+            receipts: Arc::new(RwLock::new(receipts)),
+            sent_path,
+            sent: Arc::new(RwLock::new(sent)),
+            prom,
         })
     }
 
-    async fn confirm(&self, tx: &[u8; 32]) -> Result<[u8; 32]> {
+    async fn send(&self, id: &str, hash: &[u8; 32], nonce: u64, gas: u64, fees: &Eip1559Estimation) -> Result<TxHash> {
+        let call = self
+            .instance
+            .store(id.to_string(), hash.into())
+            .nonce(nonce)
+            .gas(gas)
+            .max_fee_per_gas(fees.max_fee_per_gas)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .send()
+            .await?;
+        Ok(*call.tx_hash())
+    }
+
+    /// Stores the digest with `nonce` and waits until one of the transactions sent for
+    /// it is confirmed, or reverted. A transaction not mined in time is replaced by one
+    /// for the same nonce with bumped fees, as long as the fee cap and the gas budget
+    /// allow; one the node no longer knows, for example after a reorg took its block
+    /// away, is sent again the same way. `reserved` is the gas budget booked for the
+    /// transactions sent, whose hashes are collected in `sent`. Failed RPC calls are
+    /// retried until the confirmation timeout.
+    #[allow(clippy::too_many_arguments)]
+    async fn store(
+        &self, id: &str, hash: &[u8; 32], nonce: u64, gas: u64, mut fees: Eip1559Estimation, reserved: &mut u128, sent: &mut Vec<TxHash>,
+    ) -> Result<TransactionReceipt> {
+        let tx = self.send(id, hash, nonce, gas, &fees).await?;
+        self.sent(id, tx).await;
+        sent.push(tx);
+        let start = Instant::now();
+        let mut since = start;
+        let mut included: Option<TransactionReceipt> = None;
+
         let mut interval = tokio::time::interval(Duration::from_millis(500));
         loop {
             interval.tick().await;
-            match self.provider.get_transaction_receipt(tx.into()).await {
-                Ok(Some(receipt)) => return Ok(receipt.transaction_hash.0),
+            if start.elapsed() >= self.confirmation.timeout {
+                bail!("transaction with nonce {} was not confirmed within {:?}", nonce, self.confirmation.timeout);
+            }
+
+            match self.mined(sent).await {
+                Ok(Some(receipt)) if !receipt.status() => return Ok(receipt),
+                Ok(Some(receipt)) => {
+                    match self.confirmed(&receipt).await {
+                        Ok(true) => return Ok(receipt),
+                        Ok(false) => {}
+                        Err(err) => warn!(%id, nonce, ?err, "Failed to check whether the transaction is confirmed"),
+                    }
+                    if let Some(previous) = &included {
+                        if previous.block_hash != receipt.block_hash {
+                            warn!(%id, nonce, tx = %receipt.transaction_hash, block = ?receipt.block_number, "Transaction moved to another block");
+                            self.prom.signer_reorgs_total.inc();
+                        }
+                    }
+                    included = Some(receipt);
+                    continue;
+                }
+                Ok(None) => {
+                    if let Some(previous) = included.take() {
+                        warn!(%id, nonce, tx = %previous.transaction_hash, block = ?previous.block_number, "Transaction fell out of the canonical chain");
+                        self.prom.signer_reorgs_total.inc();
+                        since = Instant::now();
+                    }
+                }
+                Err(err) => {
+                    warn!(%id, nonce, ?err, "Failed to look up the transactions sent");
+                    continue;
+                }
+            }
+
+            match self.nonces.mined().await {
+                Ok(mined) if mined > nonce => match self.mined(sent).await {
+                    Ok(None) => bail!("nonce {} was used by another transaction of the signer account", nonce),
+                    // Our nonce may have been mined between the two checks.
+                    Ok(Some(_)) => continue,
+                    Err(err) => {
+                        warn!(%id, nonce, ?err, "Failed to look up the transactions sent");
+                        continue;
+                    }
+                },
+                Ok(_) => {}
+                Err(err) => {
+                    warn!(%id, nonce, ?err, "Failed to check the mined nonce of the signer account");
+                    continue;
+                }
+            }
+            if since.elapsed() < self.fees.replace_after {
+                continue;
+            }
+            since = Instant::now();
+
+            let Some(bumped) = self.fees.bump(&fees, self.fees.fees(&self.provider).await.ok()) else {
+                warn!(%id, nonce, max_fee = fees.max_fee_per_gas, "Transaction is stuck at the fee cap, waiting for it to be mined");
+                continue;
+            };
+            let extra = gas as u128 * (bumped.max_fee_per_gas - fees.max_fee_per_gas);
+            if !self.budget.try_reserve(extra).await {
+                warn!(%id, nonce, "Transaction is stuck but the gas budget does not cover a replacement");
+                continue;
+            }
+
+            let last = *sent.last().unwrap();
+            match self.provider.get_transaction_by_hash(last).await {
+                Ok(Some(_)) => {
+                    warn!(%id, nonce, tx = %last, "Transaction is stuck, replacing it with higher fees");
+                    self.prom.signer_tx_replacements_total.inc();
+                }
+                Ok(None) => {
+                    warn!(%id, nonce, tx = %last, "Transaction was dropped from the mempool, sending it again");
+                    self.prom.signer_nonce_gaps_total.inc();
+                }
+                Err(err) => {
+                    self.budget.release(extra).await;
+                    warn!(%id, nonce, tx = %last, ?err, "Failed to look up the stuck transaction");
+                    continue;
+                }
+            }
+            match self.send(id, hash, nonce, gas, &bumped).await {
+                Ok(tx) => {
+                    self.sent(id, tx).await;
+                    sent.push(tx);
+                    fees = bumped;
+                    *reserved += extra;
+                }
+                // Most likely the nonce was mined meanwhile, which the next round tells.
+                Err(err) => {
+                    self.budget.release(extra).await;
+                    warn!(%id, nonce, ?err, "Failed to replace transaction");
+                }
+            }
+        }
+    }
+
+    /// Settles the booking of transactions given up on while they may still be mined:
+    /// once the chain has used `nonce`, it is charged what the mined one paid, or given
+    /// back when none of them made it.
+    async fn settle(self, nonce: u64, sent: Vec<TxHash>, reserved: u128) {
+        loop {
+            sleep(SETTLE_CHECK).await;
+            match self.nonces.mined().await {
+                Ok(mined) if mined > nonce => break,
+                Ok(_) => continue,
+                Err(err) => warn!(?err, nonce, "Failed to check whether an abandoned nonce was mined"),
+            }
+        }
+
+        match self.mined(&sent).await {
+            Ok(Some(receipt)) => {
+                if let Err(err) = self.budget.charge(reserved, receipt.gas_used as u128 * receipt.effective_gas_price).await {
+                    warn!(?err, nonce, tx = %receipt.transaction_hash, "Failed to charge the gas of a late transaction");
+                }
+            }
+            Ok(None) => self.budget.release(reserved).await,
+            Err(err) => {
+                warn!(?err, nonce, "Failed to settle the gas booked for an abandoned nonce");
+                self.budget.release(reserved).await;
+            }
+        }
+    }
+
+    async fn confirmed(&self, receipt: &TransactionReceipt) -> Result<bool> {
+        let Some(block) = receipt.block_number else { return Ok(false) };
+        match self.confirmation.finality {
+            Finality::Depth(depth) => Ok(self.provider.get_block_number().await? + 1 >= block + depth),
+            Finality::Finalized => {
+                let finalized = self.provider.get_block_by_number(BlockNumberOrTag::Finalized).await?;
+                Ok(finalized.is_some_and(|finalized| finalized.header.number >= block))
+            }
+        }
+    }
+
+    /// Why the `store` of `receipt` reverted, found by replaying it on the state of
+    /// its block.
+    async fn revert_reason(&self, id: &str, hash: &[u8; 32], receipt: &TransactionReceipt) -> String {
+        let call = self.instance.store(id.to_string(), hash.into()).from(receipt.from);
+        let call = match receipt.block_hash {
+            Some(block) => call.block(BlockId::hash(block)),
+            None => call,
+        };
+        match call.call().await {
+            Ok(_) => "the call succeeds when replayed".to_string(),
+            Err(err) => match err.as_revert_data() {
+                Some(data) => decode_revert_reason(&data).unwrap_or_else(|| format!("undecoded revert data 0x{}", hex::encode(&data))),
+                None => format!("replay failed: {}", err),
+            },
+        }
+    }
+
+    async fn mined(&self, sent: &[TxHash]) -> Result<Option<TransactionReceipt>> {
+        for tx in sent {
+            match self.provider.get_transaction_receipt(*tx).await {
+                Ok(Some(receipt)) => return Ok(Some(receipt)),
                 Ok(None) => continue,
                 Err(err) => bail!("failed to get transaction receipt: {}", err),
             }
         }
+        Ok(None)
     }
 
-    async fn store(&self, id: &String, hash: &[u8; 32], nonce: u64) -> Result<()> {
-        let call = self.instance.store(id.clone(), hash.into()).nonce(nonce).send().await?;
-        let tx = call.tx_hash().0;
-        let _ = self.confirm(&tx).await?;
-        Ok(())
+    /// Records a transaction sent for a batch, so it is found again after a restart
+    /// even if it is only mined afterwards.
+    async fn sent(&self, id: &str, tx: TxHash) {
+        let mut sent = self.sent.write().await;
+        let record = SentRecord { batch_id: id.to_string(), tx_hash: tx.to_string() };
+        if let Err(err) = append_line(&self.sent_path, &record).await {
+            error!(?err, batch_id = %id, %tx, "Failed to record sent transaction");
+        }
+        sent.entry(id.to_string()).or_default().push(tx);
     }
 
-    async fn remove(&self, nonce: u64) -> Result<()> {
-        let address = self.signer.address();
-        let tx = TransactionRequest::default()
-            .with_to(address)
-            .with_nonce(nonce)
-            .with_value(U256::ZERO)
-            .with_gas_limit(21_000)
-            .with_max_priority_fee_per_gas(1_000_000_000)
-            .with_max_fee_per_gas(20_000_000_000);
-        let _ = self.provider.send_transaction(tx).await?.get_receipt().await?;
+    /// Records where the transaction anchoring `digest` for a batch was mined.
+    async fn record(&self, id: &str, digest: &[u8; 32], receipt: &TransactionReceipt) -> Result<()> {
+        let block_hash = receipt.block_hash.context("receipt of a mined transaction has no block hash")?;
+        let block = self.provider.get_block_by_hash(block_hash).await?.with_context(|| format!("block {} not found", block_hash))?;
+        let block_timestamp = DateTime::from_timestamp(block.header.timestamp as i64, 0)
+            .with_context(|| format!("block {} has an invalid timestamp", block_hash))?;
+        let anchor = AnchorReceipt {
+            batch_id: id.to_string(),
+            chain_id: self.chain_id,
+            contract: self.instance.address().to_string(),
+            tx_hash: receipt.transaction_hash.to_string(),
+            block_number: block.header.number,
+            block_hash: block_hash.to_string(),
+            block_timestamp,
+            signer: self.address.to_string(),
+            digest: Some(hex::encode(digest)),
+        };
+
+        let mut receipts = self.receipts.write().await;
+        append_line(&self.receipts_path, &anchor).await?;
+        receipts.insert(anchor.batch_id.clone(), anchor);
+        self.sent.write().await.remove(id);
         Ok(())
     }
 
-    async fn exists(&self, id: &String) -> Result<bool> {
-        match self.instance.exists(id.clone()).call().await {
+    /// Receipt of the successful transaction that anchored `id`: one of `sent` if it
+    /// was mined, otherwise the one that emitted the contract's `IndexStored` event
+    /// for it, searched from the newest blocks back to the contract deployment.
+    async fn locate(&self, id: &str, sent: &[TxHash]) -> Result<Option<TransactionReceipt>> {
+        for tx in sent {
+            if let Some(receipt) = self.provider.get_transaction_receipt(*tx).await? {
+                if receipt.status() {
+                    return Ok(Some(receipt));
+                }
+            }
+        }
+
+        let mut to = self.provider.get_block_number().await?;
+        while to >= self.deployment_block {
+            let from = to.saturating_sub(LOG_RANGE - 1).max(self.deployment_block);
+            let logs = self.instance.IndexStored_filter().topic1(keccak256(id)).from_block(from).to_block(to).query().await?;
+            if let Some(tx) = logs.last().and_then(|(_, log)| log.transaction_hash) {
+                return Ok(self.provider.get_transaction_receipt(tx).await?);
+            }
+            if from == 0 {
+                break;
+            }
+            to = from - 1;
+        }
+        Ok(None)
+    }
+
+    /// Records the receipt of a batch an earlier attempt anchored, once its transaction
+    /// is confirmed: it may have been mined after timing out, or recording it failed.
+    async fn recover(&self, id: &str, digest: &[u8; 32]) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            let sent = self.sent.read().await.get(id).cloned().unwrap_or_default();
+            let Some(receipt) = self.locate(id, &sent).await? else {
+                warn!(batch_id = %id, "Batch is anchored but the transaction that anchored it was not found");
+                return Ok(());
+            };
+            if self.confirmed(&receipt).await? {
+                return self.record(id, digest, &receipt).await;
+            }
+            if start.elapsed() >= self.confirmation.timeout {
+                bail!(
+                    "transaction {} anchoring batch {} was not confirmed within {:?}",
+                    receipt.transaction_hash,
+                    id,
+                    self.confirmation.timeout
+                );
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Anchors `digest` for a batch again after a reorg took its transaction out of the
+    /// canonical chain for good.
+    async fn republish(self, id: String, digest: [u8; 32]) {
+        let batch = Batch { id, documents: Vec::new(), digest, hash: HashSpec::default(), link: None };
+        match self.publish(&batch).await {
+            Ok(()) => info!(batch_id = %batch.id, "Batch anchored again after a reorg"),
+            Err(err) => error!(?err, batch_id = %batch.id, "Failed to anchor batch again after a reorg"),
+        }
+    }
+
+    async fn exists(&self, id: &str) -> Result<bool> {
+        match self.instance.exists(id.to_string()).call().await {
             Ok(exists) => Ok(exists._0),
             Err(err) => bail!("failed to call contract function `exists` with id `{}`: {:?}", id, err),
         }
@@ -104,34 +466,108 @@ impl SignerRepository for EthereumSignerRepository {
     async fn publish(&self, batch: &Batch) -> Result<()> {
         let _permit = self.max_tx_pending.clone().acquire_owned().await?;
 
-        let nonce = self.nonce.fetch_add(1, Ordering::SeqCst);
         let mut attempts = 0;
         let max_attempts = 3;
 
         loop {
+            // An earlier attempt may have been mined after all.
+            if let Some(digest) = self.digest(&batch.id).await? {
+                if digest == batch.digest {
+                    if !self.receipts.read().await.contains_key(&batch.id) {
+                        self.recover(&batch.id, &digest).await?;
+                    }
+                    return Ok(());
+                }
+                bail!("batch {} is already anchored with a different digest", batch.id);
+            }
+
             attempts += 1;
-            match self.store(&batch.id, &batch.digest, nonce).await {
-                Ok(()) => break,
-                Err(_) if attempts <= max_attempts => {
-                    sleep(Duration::from_millis(100 * attempts)).await;
+            let fees = self.fees.fees(&self.provider).await?;
+            let gas = self.instance.store(batch.id.clone(), batch.digest.into()).estimate_gas().await?;
+            let mut reserved = gas as u128 * fees.max_fee_per_gas;
+            self.budget.reserve(reserved).await?;
+
+            let nonce = self.nonces.reserve().await;
+            let mut sent = Vec::new();
+            let stored = self.store(&batch.id, &batch.digest, nonce, gas, fees, &mut reserved, &mut sent).await;
+            match stored {
+                Ok(receipt) => {
+                    self.nonces.complete(nonce).await;
+                    self.budget.charge(reserved, receipt.gas_used as u128 * receipt.effective_gas_price).await?;
+                    if !receipt.status() {
+                        self.prom.signer_reverts_total.inc();
+                        let reason = self.revert_reason(&batch.id, &batch.digest, &receipt).await;
+                        bail!("transaction {} storing batch {} reverted: {}", receipt.transaction_hash, batch.id, reason);
+                    }
+                    debug!(batch_id = %batch.id, nonce, tx = %receipt.transaction_hash, "Batch anchored");
+                    return self.record(&batch.id, &batch.digest, &receipt).await;
                 }
                 Err(err) => {
-                    self.remove(nonce).await?;
-                    bail!("failed to send transaction after {} attempts: {}", attempts, err)
+                    if sent.is_empty() {
+                        self.budget.release(reserved).await;
+                    } else {
+                        // What was sent may still be mined, for example after a timeout.
+                        tokio::spawn(self.clone().settle(nonce, sent, reserved));
+                    }
+                    if let Err(err) = self.nonces.release(nonce).await {
+                        warn!(?err, "Failed to resync signer nonces");
+                    }
+                    if attempts > max_attempts {
+                        bail!("failed to send transaction after {} attempts: {}", attempts, err)
+                    }
+                    sleep(Duration::from_millis(100 * attempts)).await;
                 }
             }
         }
-
-        Ok(())
     }
 
-    async fn digest(&self, id: &String) -> Result<Option<[u8; 32]>> {
+    async fn digest(&self, id: &str) -> Result<Option<[u8; 32]>> {
         if !self.exists(id).await? {
             return Ok(None);
         }
-        match self.instance.hash(id.clone()).call().await {
+        match self.instance.hash(id.to_string()).call().await {
             Ok(hash) => Ok(Some(hash._0.0)),
             Err(err) => bail!("failed to call contract function `hash` with id `{}`: {:?}", id, err),
         }
     }
+
+    /// The recorded receipt, looked up again when a reorg has taken its block out of
+    /// the canonical chain since it was recorded. A batch no longer anchored at all is
+    /// published again in the background, and has no receipt until that is mined.
+    async fn anchor(&self, id: &str) -> Result<Option<AnchorReceipt>> {
+        let Some(anchor) = self.receipts.read().await.get(id).cloned() else { return Ok(None) };
+        let canonical = self.provider.get_block_by_number(BlockNumberOrTag::Number(anchor.block_number)).await?;
+        if canonical.is_some_and(|block| block.header.hash.to_string() == anchor.block_hash) {
+            return Ok(Some(anchor));
+        }
+
+        warn!(batch_id = %id, block = anchor.block_number, tx = %anchor.tx_hash, "Anchoring block is no longer canonical, looking the transaction up again");
+        if let Some(receipt) = self.locate(id, &[anchor.tx_hash.parse()?]).await? {
+            if let Some(digest) = self.digest(id).await? {
+                self.record(id, &digest, &receipt).await?;
+                return Ok(self.receipts.read().await.get(id).cloned());
+            }
+        }
+
+        let Some(digest) = anchor.digest.as_deref().and_then(decode) else {
+            warn!(batch_id = %id, "Batch is no longer anchored on the canonical chain and its receipt has no digest to anchor again");
+            return Ok(None);
+        };
+        // Only the request that drops the stale receipt publishes the batch again.
+        if self.receipts.write().await.remove(id).is_some() {
+            warn!(batch_id = %id, "Batch is no longer anchored on the canonical chain, publishing it again");
+            tokio::spawn(self.clone().republish(id.to_string(), digest));
+        }
+        Ok(None)
+    }
+
+    fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+}
+
+fn decode(encoded: &str) -> Option<[u8; 32]> {
+    let mut digest = [0u8; 32];
+    hex::decode_to_slice(encoded, &mut digest).ok()?;
+    Some(digest)
 }
@@ -0,0 +1,79 @@
+use alloy::{
+    eips::BlockNumberOrTag,
+    providers::{utils::Eip1559Estimation, DynProvider, Provider},
+};
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+/// How the EIP-1559 fees of a new transaction are chosen.
+#[derive(Clone, Debug)]
+pub enum FeeStrategy {
+    /// Whatever the node's fee estimator suggests.
+    Estimate,
+    /// The given priority fee percentile over the last `blocks` blocks, on top of
+    /// twice the next base fee.
+    Percentile { percentile: f64, blocks: u64 },
+    /// Always the capped maximum fee, with a fixed priority fee.
+    Fixed { priority_fee: u128 },
+}
+
+/// Fees of the signer transactions. `max_fee` caps the fee per gas of every
+/// transaction, replacements included, whatever the strategy. A transaction not
+/// mined within `replace_after` is replaced with fees bumped by `bump_percent`.
+#[derive(Clone, Debug)]
+pub struct FeePolicy {
+    pub strategy: FeeStrategy,
+    pub max_fee: u128,
+    pub bump_percent: u64,
+    pub replace_after: Duration,
+}
+
+impl FeePolicy {
+    pub async fn fees(&self, provider: &DynProvider) -> Result<Eip1559Estimation> {
+        let fees = match &self.strategy {
+            FeeStrategy::Estimate => provider.estimate_eip1559_fees().await?,
+            FeeStrategy::Percentile { percentile, blocks } => {
+                let history = provider.get_fee_history(*blocks, BlockNumberOrTag::Latest, &[*percentile]).await?;
+                let base_fee = history.next_block_base_fee().context("node returned no base fee history")?;
+                let mut rewards: Vec<u128> = history
+                    .reward
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|block| block.first().copied())
+                    .filter(|&reward| reward > 0)
+                    .collect();
+                rewards.sort_unstable();
+                let priority_fee = match rewards.get(rewards.len() / 2) {
+                    Some(&reward) => reward,
+                    // Recent blocks were empty, so there is nothing to outbid.
+                    None => provider.estimate_eip1559_fees().await?.max_priority_fee_per_gas,
+                };
+                Eip1559Estimation { max_fee_per_gas: 2 * base_fee + priority_fee, max_priority_fee_per_gas: priority_fee }
+            }
+            FeeStrategy::Fixed { priority_fee } => {
+                Eip1559Estimation { max_fee_per_gas: self.max_fee, max_priority_fee_per_gas: *priority_fee }
+            }
+        };
+        Ok(self.cap(fees))
+    }
+
+    /// Fees replacing a transaction sent with `fees`, bumped by `bump_percent` or to
+    /// the current `estimate` if the market moved further. `None` once the cap leaves
+    /// no room for a bump the node would accept.
+    pub fn bump(&self, fees: &Eip1559Estimation, estimate: Option<Eip1559Estimation>) -> Option<Eip1559Estimation> {
+        let bump = |fee: u128| fee + (fee * self.bump_percent as u128).div_ceil(100).max(1);
+        let estimate = estimate.unwrap_or(Eip1559Estimation { max_fee_per_gas: 0, max_priority_fee_per_gas: 0 });
+        let bumped = Eip1559Estimation {
+            max_fee_per_gas: bump(fees.max_fee_per_gas).max(estimate.max_fee_per_gas),
+            max_priority_fee_per_gas: bump(fees.max_priority_fee_per_gas).max(estimate.max_priority_fee_per_gas),
+        };
+        let capped = self.cap(bumped);
+        (capped.max_fee_per_gas >= bump(fees.max_fee_per_gas) && capped.max_priority_fee_per_gas >= bump(fees.max_priority_fee_per_gas))
+            .then_some(capped)
+    }
+
+    fn cap(&self, fees: Eip1559Estimation) -> Eip1559Estimation {
+        let max_fee_per_gas = fees.max_fee_per_gas.min(self.max_fee);
+        Eip1559Estimation { max_fee_per_gas, max_priority_fee_per_gas: fees.max_priority_fee_per_gas.min(max_fee_per_gas) }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    domain::{Batch, HashAlgorithm, SignerAudit, SignerIssue, SignerIssueKind, SignerRepository},
+    infra::helper::hash,
+};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{sync::Mutex, task::spawn_blocking};
+use tracing::{info, warn};
+
+const RECORD_DOMAIN: &[u8] = b"audita-signer-v1\0";
+/// Records are a few hundred bytes, so the last newline is always within this tail.
+const TAIL_BYTES: u64 = 64 * 1024;
+
+/// One anchored batch. `hash` covers every other field except the signature, and
+/// `previous` is the hash of the record before it.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    sequence: u64,
+    id: String,
+    digest: String,
+    timestamp: DateTime<Utc>,
+    previous: Option<String>,
+    hash: String,
+    signature: String,
+}
+
+/// Last record written, kept next to the records so truncation can be told apart
+/// from a signer that never wrote more.
+#[derive(Serialize, Deserialize)]
+struct Head {
+    sequence: u64,
+    hash: String,
+    signature: String,
+}
+
+struct State {
+    next: u64,
+    last: Option<[u8; 32]>,
+    index: HashMap<String, [u8; 32]>,
+}
+
+/// Anchors batch digests in a local append-only file instead of a blockchain. Every
+/// record is hash-chained to the one before it and signed with an Ed25519 key, so
+/// rewriting, reordering or dropping records is detected by `audit`.
+#[derive(Clone)]
+pub struct FileSignerRepository {
+    path: PathBuf,
+    key: Arc<SigningKey>,
+    state: Arc<Mutex<State>>,
+}
+
+impl FileSignerRepository {
+    /// Opens the record file at `path`, signing with the hex-encoded seed in `key_path`.
+    /// A missing key is generated. Refuses to start on records that fail verification.
+    pub fn new(path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create signer directory {}", dir.display()))?;
+        }
+        let key = load_key(key_path.as_ref())?;
+        info!(public_key = %hex::encode(key.verifying_key().as_bytes()), "File signer ready");
+
+        repair_tail(&path)?;
+        let scan = scan(&path, &key.verifying_key())?;
+        if let Some(issue) = scan.issues.first() {
+            bail!("signer records {} failed verification at sequence {}: {}", path.display(), issue.sequence, issue.detail);
+        }
+
+        let state = State { next: scan.records, last: scan.last, index: scan.index };
+        Ok(Self { path, key: Arc::new(key), state: Arc::new(Mutex::new(state)) })
+    }
+
+    fn head_path(&self) -> PathBuf {
+        head_path(&self.path)
+    }
+}
+
+#[async_trait]
+impl SignerRepository for FileSignerRepository {
+    async fn publish(&self, batch: &Batch) -> Result<()> {
+        let mut state = self.state.lock().await;
+        if let Some(digest) = state.index.get(&batch.id) {
+            if *digest == batch.digest {
+                return Ok(());
+            }
+            bail!("batch {} is already anchored with a different digest", batch.id);
+        }
+
+        let timestamp = Utc::now().trunc_subsecs(6);
+        let hash = record_hash(state.next, &batch.id, &batch.digest, timestamp, state.last.as_ref());
+        let signature = hex::encode(self.key.sign(&hash).to_bytes());
+        let record = Record {
+            sequence: state.next,
+            id: batch.id.clone(),
+            digest: hex::encode(batch.digest),
+            timestamp,
+            previous: state.last.map(hex::encode),
+            hash: hex::encode(hash),
+            signature: signature.clone(),
+        };
+        let head = Head { sequence: record.sequence, hash: record.hash.clone(), signature };
+
+        let path = self.path.clone();
+        spawn_blocking(move || append_record(&path, &record)).await??;
+
+        // The record is durable from here on, so the state advances even if the head
+        // cannot be written; it only lags behind until the next record.
+        state.next += 1;
+        state.last = Some(hash);
+        state.index.insert(batch.id.clone(), batch.digest);
+
+        let head_path = self.head_path();
+        if let Err(err) = spawn_blocking(move || write_head(&head_path, &head)).await? {
+            warn!(?err, batch_id = %batch.id, "Failed to write signer head");
+        }
+        Ok(())
+    }
+
+    async fn digest(&self, id: &str) -> Result<Option<[u8; 32]>> {
+        Ok(self.state.lock().await.index.get(id).copied())
+    }
+
+    async fn audit(&self) -> Result<Option<SignerAudit>> {
+        // Holding the lock keeps appends from racing the scan.
+        let _state = self.state.lock().await;
+        let (path, public_key) = (self.path.clone(), self.key.verifying_key());
+        let scan = spawn_blocking(move || scan(&path, &public_key)).await??;
+
+        Ok(Some(SignerAudit {
+            backend: "file".into(),
+            public_key: hex::encode(self.key.verifying_key().as_bytes()),
+            records: scan.records,
+            head: scan.last.map(hex::encode),
+            verified: scan.issues.is_empty(),
+            issues: scan.issues,
+        }))
+    }
+}
+
+fn record_hash(sequence: u64, id: &str, digest: &[u8; 32], timestamp: DateTime<Utc>, previous: Option<&[u8; 32]>) -> [u8; 32] {
+    let (flag, previous) = match previous {
+        Some(previous) => ([1u8], *previous),
+        None => ([0u8], [0u8; 32]),
+    };
+    hash(
+        HashAlgorithm::Sha256,
+        &[
+            RECORD_DOMAIN,
+            &sequence.to_be_bytes(),
+            &(id.len() as u64).to_be_bytes(),
+            id.as_bytes(),
+            digest,
+            &timestamp.timestamp_micros().to_be_bytes(),
+            &flag,
+            &previous,
+        ],
+    )
+}
+
+/// Appends `record` as one synced line. A failed append is cut off again, so the
+/// file never holds a partial record that `new` would refuse to start on.
+fn append_record(path: &Path, record: &Record) -> Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let len = file.metadata()?.len();
+    if let Err(err) = file.write_all(&line).and_then(|()| file.sync_data()) {
+        file.set_len(len)?;
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+fn write_head(path: &Path, head: &Head) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec(head)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn head_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".head");
+    path.with_file_name(name)
+}
+
+fn load_key(path: &Path) -> Result<SigningKey> {
+    match fs::read_to_string(path) {
+        Ok(encoded) => {
+            let mut seed = [0u8; 32];
+            hex::decode_to_slice(encoded.trim(), &mut seed).with_context(|| format!("invalid Ed25519 key {}", path.display()))?;
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let mut seed = [0u8; 32];
+            rand::rngs::OsRng.fill_bytes(&mut seed);
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)?;
+            file.write_all(hex::encode(seed).as_bytes())?;
+            file.sync_all()?;
+            info!(path = %path.display(), "Generated a new Ed25519 signing key");
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Cuts off a last record left incomplete by a crash. It was never acknowledged, as
+/// records are synced before `publish` returns.
+fn repair_tail(path: &Path) -> Result<()> {
+    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(TAIL_BYTES);
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.read_to_end(&mut tail)?;
+    if let Some(end) = tail.iter().rposition(|&b| b == b'\n').map(|i| start + i as u64 + 1).or((start == 0).then_some(0)) {
+        if end < len {
+            file.set_len(end)?;
+        }
+    }
+    Ok(())
+}
+
+struct Scan {
+    records: u64,
+    last: Option<[u8; 32]>,
+    index: HashMap<String, [u8; 32]>,
+    issues: Vec<SignerIssue>,
+}
+
+/// Walks every record checking its sequence, link, hash and signature, then checks
+/// that the file still reaches the persisted head.
+fn scan(path: &Path, public_key: &VerifyingKey) -> Result<Scan> {
+    let mut scan = Scan { records: 0, last: None, index: HashMap::new(), issues: Vec::new() };
+    let issue = |issues: &mut Vec<SignerIssue>, kind, sequence, detail: String| issues.push(SignerIssue { kind, sequence, detail });
+
+    let head: Option<Head> = match fs::read(head_path(path)) {
+        Ok(bytes) => Some(serde_json::from_slice(&bytes).context("invalid signer head")?),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    let mut head_hash = None;
+
+    let reader = match File::open(path) {
+        Ok(file) => Some(BufReader::new(file)),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    for line in reader.into_iter().flat_map(|reader| reader.lines()) {
+        let line = line?;
+        let expected = scan.records;
+        scan.records += 1;
+
+        let record: Record = match serde_json::from_str(&line) {
+            Ok(record) => record,
+            Err(err) => {
+                issue(&mut scan.issues, SignerIssueKind::Malformed, expected, err.to_string());
+                scan.last = None;
+                continue;
+            }
+        };
+        if record.sequence != expected {
+            let detail = format!("record {} found where {} was expected", record.sequence, expected);
+            issue(&mut scan.issues, SignerIssueKind::OutOfSequence, expected, detail);
+            scan.records = record.sequence + 1;
+        }
+        if record.previous != scan.last.map(hex::encode) {
+            issue(&mut scan.issues, SignerIssueKind::BrokenLink, record.sequence, "does not point at the previous record".into());
+        }
+        let (Some(digest), Some(hash)) = (decode(&record.digest), decode(&record.hash)) else {
+            issue(&mut scan.issues, SignerIssueKind::Malformed, record.sequence, "digest or hash is not 32 hex-encoded bytes".into());
+            scan.last = None;
+            continue;
+        };
+        let previous = record.previous.as_deref().and_then(decode);
+        if record_hash(record.sequence, &record.id, &digest, record.timestamp, previous.as_ref()) != hash {
+            issue(&mut scan.issues, SignerIssueKind::Rewritten, record.sequence, format!("record for batch {} was modified", record.id));
+        }
+        if !signed(public_key, &hash, &record.signature) {
+            issue(&mut scan.issues, SignerIssueKind::BadSignature, record.sequence, format!("record for batch {}", record.id));
+        }
+
+        if head.as_ref().is_some_and(|head| head.sequence == record.sequence) {
+            head_hash = Some(record.hash);
+        }
+        scan.last = Some(hash);
+        scan.index.entry(record.id).or_insert(digest);
+    }
+
+    if let Some(head) = head {
+        if !decode(&head.hash).is_some_and(|hash| signed(public_key, &hash, &head.signature)) {
+            issue(&mut scan.issues, SignerIssueKind::BadSignature, head.sequence, "head was not written by this signer".into());
+        } else if head_hash.as_ref() != Some(&head.hash) {
+            let detail = format!("last record written was {}, the file holds {}", head.sequence, scan.records);
+            issue(&mut scan.issues, SignerIssueKind::Truncated, head.sequence, detail);
+        }
+    }
+    Ok(scan)
+}
+
+fn signed(public_key: &VerifyingKey, hash: &[u8; 32], signature: &str) -> bool {
+    let mut bytes = [0u8; 64];
+    hex::decode_to_slice(signature, &mut bytes).is_ok() && public_key.verify(hash, &Signature::from_bytes(&bytes)).is_ok()
+}
+
+fn decode(encoded: &str) -> Option<[u8; 32]> {
+    let mut digest = [0u8; 32];
+    hex::decode_to_slice(encoded, &mut digest).ok()?;
+    Some(digest)
+}
@@ -4,14 +4,16 @@ use async_trait::async_trait;
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
 
-#[derive(Clone)]
+/// Keeps anchored digests in process memory, for development and tests without a
+/// blockchain node. Nothing survives a restart.
+#[derive(Clone, Default)]
 pub struct MemorySignerRepository {
     digests: Arc<RwLock<HashMap<String, [u8; 32]>>>,
 }
 
 impl MemorySignerRepository {
     pub fn new() -> Self {
-        Self { digests: Arc::new(RwLock::new(HashMap::new())) }
+        Self::default()
     }
 }
 
@@ -23,7 +25,7 @@ impl SignerRepository for MemorySignerRepository {
         Ok(())
     }
 
-    async fn digest(&self, id: &String) -> Result<Option<[u8; 32]>> {
+    async fn digest(&self, id: &str) -> Result<Option<[u8; 32]>> {
         Ok(self.digests.read().await.get(id).cloned())
     }
 }
@@ -1,5 +1,16 @@
+mod aggregate;
+mod budget;
 mod ethereum;
-// mod memory;
+mod fees;
+mod file;
+mod memory;
+mod nonce;
+mod rfc3161;
 
+pub use aggregate::*;
+pub use budget::*;
 pub use ethereum::*;
-// pub use memory::*;
+pub use fees::*;
+pub use file::*;
+pub use memory::*;
+pub use rfc3161::*;
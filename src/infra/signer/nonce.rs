@@ -0,0 +1,103 @@
+use crate::infra::prometheus::Prometheus;
+use alloy::{
+    primitives::Address,
+    providers::{DynProvider, Provider},
+};
+use anyhow::Result;
+use std::collections::BTreeSet;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+struct State {
+    /// Next nonce never handed out.
+    next: u64,
+    /// Nonces of transactions sent and not yet mined.
+    in_flight: BTreeSet<u64>,
+    /// Nonces handed back without a transaction in the mempool, reused first so they
+    /// do not leave a gap.
+    released: BTreeSet<u64>,
+}
+
+/// Hands out the account nonces of the signer. A nonce is either in flight until its
+/// transaction is mined, or released to be reused when its transaction never made it
+/// to the mempool. The count of pending transactions reported by the node is the
+/// source of truth whenever something goes wrong.
+pub struct NonceManager {
+    provider: DynProvider,
+    address: Address,
+    prom: Prometheus,
+    state: Mutex<State>,
+}
+
+impl NonceManager {
+    pub async fn new(provider: DynProvider, address: Address, prom: Prometheus) -> Result<Self> {
+        let next = provider.get_transaction_count(address).pending().await?;
+        let state = State { next, in_flight: BTreeSet::new(), released: BTreeSet::new() };
+        Ok(Self { provider, address, prom, state: Mutex::new(state) })
+    }
+
+    pub async fn reserve(&self) -> u64 {
+        let mut state = self.state.lock().await;
+        let nonce = match state.released.pop_first() {
+            Some(nonce) => nonce,
+            None => {
+                state.next += 1;
+                state.next - 1
+            }
+        };
+        state.in_flight.insert(nonce);
+        self.prom.signer_nonces_in_flight.set(state.in_flight.len() as f64);
+        nonce
+    }
+
+    /// The transaction holding `nonce` was mined.
+    pub async fn complete(&self, nonce: u64) {
+        let mut state = self.state.lock().await;
+        state.in_flight.remove(&nonce);
+        self.prom.signer_nonces_in_flight.set(state.in_flight.len() as f64);
+    }
+
+    /// No transaction holding `nonce` is waiting to be mined any more, either because
+    /// it never reached the mempool or because it was dropped or replaced. The nonce is
+    /// handed out again unless the resync shows the chain has already moved past it.
+    pub async fn release(&self, nonce: u64) -> Result<()> {
+        let mut state = self.state.lock().await;
+        if state.in_flight.remove(&nonce) && nonce < state.next {
+            state.released.insert(nonce);
+        }
+        self.prom.signer_nonces_in_flight.set(state.in_flight.len() as f64);
+        self.resync(&mut state).await
+    }
+
+    /// Lines the nonces up with the account's pending transaction count. Released
+    /// nonces the chain has moved past were used elsewhere and are dropped; nonces
+    /// above it that nothing holds are a gap, and are handed out again.
+    async fn resync(&self, state: &mut State) -> Result<()> {
+        let pending = self.provider.get_transaction_count(self.address).pending().await?;
+        self.prom.signer_nonce_resyncs_total.inc();
+
+        state.released.retain(|&nonce| nonce >= pending);
+        if pending > state.next {
+            warn!(expected = state.next, pending, "Signer account was used outside of audita, skipping ahead");
+            state.next = pending;
+            return Ok(());
+        }
+
+        let gaps: Vec<u64> =
+            (pending..state.next).filter(|nonce| !state.in_flight.contains(nonce) && !state.released.contains(nonce)).collect();
+        if !gaps.is_empty() {
+            info!(pending, next = state.next, gaps = gaps.len(), "Closing nonce gaps left by dropped transactions");
+            self.prom.signer_nonce_gaps_total.inc_by(gaps.len() as f64);
+            state.released.extend(gaps);
+        }
+        while state.next > pending && state.released.remove(&(state.next - 1)) {
+            state.next -= 1;
+        }
+        Ok(())
+    }
+
+    /// Number of transactions of the account mined so far.
+    pub async fn mined(&self) -> Result<u64> {
+        Ok(self.provider.get_transaction_count(self.address).latest().await?)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    domain::{Batch, SignerRepository},
+    infra::helper::{load_certificate, timestamp_request, validate_response, LocalTimestampAuthority},
+};
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
+use reqwest::{header::CONTENT_TYPE, Client};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{debug, info};
+use x509_cert::Certificate;
+
+enum Authority {
+    Local(Box<LocalTimestampAuthority>),
+    Remote { client: Client, url: String },
+}
+
+/// Anchors batch digests with RFC 3161 timestamp tokens, issued by a remote timestamp
+/// authority or by a local one signing with a configured certificate. The
+/// TimeStampResp of each batch is kept as `<batch_id>.tsr`, so it can be handed out
+/// and checked with `openssl ts -verify`.
+#[derive(Clone)]
+pub struct Rfc3161SignerRepository {
+    dir: PathBuf,
+    certificate: Arc<Certificate>,
+    authority: Arc<Authority>,
+}
+
+impl Rfc3161SignerRepository {
+    /// Issues tokens locally, signing with the PEM certificate and key under `policy`.
+    pub fn local(dir: impl AsRef<Path>, certificate_path: &str, key_path: &str, policy: &str) -> Result<Self> {
+        let certificate = read_certificate(certificate_path)?;
+        let key = std::fs::read_to_string(key_path).with_context(|| format!("failed to read timestamp key {}", key_path))?;
+        let authority = LocalTimestampAuthority::new(certificate.clone(), &key, policy)?;
+        Self::open(dir.as_ref(), certificate, Authority::Local(Box::new(authority)))
+    }
+
+    /// Requests tokens from the timestamp authority at `url`, accepting only tokens
+    /// signed with the pinned PEM certificate.
+    pub fn remote(dir: impl AsRef<Path>, certificate_path: &str, url: String, timeout: Duration) -> Result<Self> {
+        let certificate = read_certificate(certificate_path)?;
+        let client = Client::builder().timeout(timeout).build()?;
+        Self::open(dir.as_ref(), certificate, Authority::Remote { client, url })
+    }
+
+    fn open(dir: &Path, certificate: Certificate, authority: Authority) -> Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create token directory {}", dir.display()))?;
+        info!(subject = %certificate.tbs_certificate.subject, "RFC 3161 signer ready");
+        Ok(Self { dir: dir.to_path_buf(), certificate: Arc::new(certificate), authority: Arc::new(authority) })
+    }
+
+    /// Token file of a batch, or `None` for ids that cannot name one safely.
+    fn token_path(&self, id: &str) -> Option<PathBuf> {
+        let safe = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        safe.then(|| self.dir.join(format!("{}.tsr", id)))
+    }
+
+    async fn request(&self, request: Vec<u8>) -> Result<Vec<u8>> {
+        match self.authority.as_ref() {
+            Authority::Local(authority) => authority.respond(&request),
+            Authority::Remote { client, url } => {
+                let response =
+                    client.post(url).header(CONTENT_TYPE, "application/timestamp-query").body(request).send().await?.error_for_status()?;
+                Ok(response.bytes().await?.to_vec())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SignerRepository for Rfc3161SignerRepository {
+    async fn publish(&self, batch: &Batch) -> Result<()> {
+        let path = self.token_path(&batch.id).with_context(|| format!("batch id {} cannot name a token file", batch.id))?;
+        if let Some(digest) = self.digest(&batch.id).await? {
+            if digest == batch.digest {
+                return Ok(());
+            }
+            bail!("batch {} is already timestamped with a different digest", batch.id);
+        }
+
+        let (request, nonce) = timestamp_request(&batch.digest, batch.hash.algorithm)?;
+        let response = self.request(request).await?;
+        let timestamp = validate_response(&response, &self.certificate)?;
+        ensure!(timestamp.digest == batch.digest, "timestamp authority stamped another digest for batch {}", batch.id);
+        ensure!(timestamp.nonce.as_deref() == Some(nonce.as_slice()), "timestamp response does not echo the request nonce");
+
+        let tmp = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(&response).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, &path).await?;
+        debug!(batch_id = %batch.id, serial = %timestamp.serial, time = %timestamp.time, "Batch timestamped");
+        Ok(())
+    }
+
+    /// The timestamped hash, once the stored token validates against the certificate.
+    async fn digest(&self, id: &str) -> Result<Option<[u8; 32]>> {
+        let Some(response) = self.token(id).await? else { return Ok(None) };
+        let timestamp =
+            validate_response(&response, &self.certificate).with_context(|| format!("invalid timestamp token for batch {}", id))?;
+        Ok(Some(timestamp.digest))
+    }
+
+    async fn token(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let Some(path) = self.token_path(id) else { return Ok(None) };
+        match fs::read(&path).await {
+            Ok(response) => Ok(Some(response)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn read_certificate(path: &str) -> Result<Certificate> {
+    let pem = std::fs::read(path).with_context(|| format!("failed to read timestamp certificate {}", path))?;
+    load_certificate(&pem).with_context(|| format!("invalid timestamp certificate {}", path))
+}
@@ -0,0 +1,126 @@
+use crate::{
+    domain::{BatchEvent, BatchStatus, BatchStatusRepository},
+    infra::storage::connect,
+};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use elasticsearch::{http::StatusCode, indices::IndicesCreateParts, params::OpType, Elasticsearch, GetParts, IndexParts};
+use serde_json::{json, Value};
+use tokio::sync::OnceCell;
+
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
+/// Keeps one status document per batch in a hidden index, so it never shows up in
+/// document searches. Concurrent updates from the signer and storage tasks are
+/// serialized with optimistic concurrency control on `_seq_no`/`_primary_term`.
+pub struct ElasticsearchBatchStatusRepository {
+    client: Elasticsearch,
+    index: String,
+    ready: OnceCell<()>,
+}
+
+struct Versioned {
+    status: BatchStatus,
+    seq_no: i64,
+    primary_term: i64,
+}
+
+impl ElasticsearchBatchStatusRepository {
+    pub fn new(url: String, username: String, password: String, index: String) -> Result<Self> {
+        Ok(Self { client: connect(url, username, password)?, index, ready: OnceCell::new() })
+    }
+
+    async fn ensure_index(&self) -> Result<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                let response = self
+                    .client
+                    .indices()
+                    .create(IndicesCreateParts::Index(&self.index))
+                    .body(json!({ "settings": { "index": { "hidden": true } } }))
+                    .send()
+                    .await?;
+                let status = response.status_code();
+                if !status.is_success() {
+                    let body: Value = response.json().await?;
+                    if body["error"]["type"] != "resource_already_exists_exception" {
+                        bail!("failed to create batch status index with status {}: {}", status, body);
+                    }
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch(&self, id: &str) -> Result<Option<Versioned>> {
+        self.ensure_index().await?;
+        let response = self.client.get(GetParts::IndexId(&self.index, id)).send().await?;
+        if response.status_code() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let status = response.status_code();
+        let body: Value = response.json().await?;
+        if !status.is_success() {
+            bail!("batch status request failed with status {}: {}", status, body);
+        }
+
+        Ok(Some(Versioned {
+            status: serde_json::from_value(body["_source"].clone())?,
+            seq_no: body["_seq_no"].as_i64().unwrap_or_default(),
+            primary_term: body["_primary_term"].as_i64().unwrap_or_default(),
+        }))
+    }
+}
+
+#[async_trait]
+impl BatchStatusRepository for ElasticsearchBatchStatusRepository {
+    async fn create(&self, status: &BatchStatus) -> Result<()> {
+        self.ensure_index().await?;
+        let response = self.client.index(IndexParts::IndexId(&self.index, &status.id)).op_type(OpType::Create).body(status).send().await?;
+
+        let code = response.status_code();
+        if !code.is_success() && code != StatusCode::CONFLICT {
+            let body: Value = response.json().await?;
+            bail!("failed to create batch status with status {}: {}", code, body);
+        }
+        Ok(())
+    }
+
+    async fn update(&self, id: &str, event: BatchEvent) -> Result<BatchStatus> {
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let now = Utc::now();
+            let request = self.client.index(IndexParts::IndexId(&self.index, id));
+
+            let (request, status) = match self.fetch(id).await? {
+                Some(Versioned { mut status, seq_no, primary_term }) => {
+                    status.apply(event.clone(), now);
+                    (request.if_seq_no(seq_no).if_primary_term(primary_term), status)
+                }
+                None => {
+                    let mut status = BatchStatus::new(id.to_string(), now);
+                    status.apply(event.clone(), now);
+                    (request.op_type(OpType::Create), status)
+                }
+            };
+
+            let response = request.body(&status).send().await?;
+            let code = response.status_code();
+            if code.is_success() {
+                return Ok(status);
+            }
+            if code != StatusCode::CONFLICT {
+                let body: Value = response.json().await?;
+                bail!("failed to update batch status with status {}: {}", code, body);
+            }
+        }
+
+        bail!("failed to update batch status `{}`: too many concurrent modifications", id)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<BatchStatus>> {
+        Ok(self.fetch(id).await?.map(|versioned| versioned.status))
+    }
+}
@@ -0,0 +1,38 @@
+use crate::domain::{BatchEvent, BatchStatus, BatchStatusRepository};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+
+/// Keeps batch statuses in process memory, alongside the memory storage backend.
+#[derive(Clone, Default)]
+pub struct MemoryBatchStatusRepository {
+    statuses: Arc<RwLock<HashMap<String, BatchStatus>>>,
+}
+
+impl MemoryBatchStatusRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BatchStatusRepository for MemoryBatchStatusRepository {
+    async fn create(&self, status: &BatchStatus) -> Result<()> {
+        self.statuses.write().await.entry(status.id.clone()).or_insert_with(|| status.clone());
+        Ok(())
+    }
+
+    async fn update(&self, id: &str, event: BatchEvent) -> Result<BatchStatus> {
+        let now = Utc::now();
+        let mut statuses = self.statuses.write().await;
+        let status = statuses.entry(id.to_string()).or_insert_with(|| BatchStatus::new(id.to_string(), now));
+        status.apply(event, now);
+        Ok(status.clone())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<BatchStatus>> {
+        Ok(self.statuses.read().await.get(id).cloned())
+    }
+}
@@ -0,0 +1,5 @@
+mod elasticsearch;
+mod memory;
+
+pub use elasticsearch::*;
+pub use memory::*;
@@ -1,7 +1,10 @@
-use crate::domain::{Batch, Condition, DocumentQuery, Hasher, Operator, Query, QueryResult, StorageRepository};
+use crate::domain::{
+    Batch, ChainLink, Condition, DigestStream, DocumentQuery, HashSpec, Hasher, LinkedBatch, Operator, Query, QueryResult, SortOrder,
+    StorageRepository, StoredDigest,
+};
 use anyhow::{bail, Ok, Result};
 use async_trait::async_trait;
-use chrono::Local;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
 use elasticsearch::{
     auth::Credentials,
     cert::CertificateValidation,
@@ -13,6 +16,16 @@ use std::sync::Arc;
 
 const AUDITA_ID_KEYWORD: &str = "audita_id";
 const AUDITA_ORD_KEYWORD: &str = "audita_ord";
+const AUDITA_HASH_ALGORITHM_KEYWORD: &str = "audita_hash_algorithm";
+const AUDITA_HASH_SCHEME_KEYWORD: &str = "audita_hash_scheme";
+const AUDITA_CANONICALIZATION_KEYWORD: &str = "audita_canonicalization";
+const AUDITA_CHAIN_STREAM_KEYWORD: &str = "audita_chain_stream";
+const AUDITA_CHAIN_SEQUENCE_KEYWORD: &str = "audita_chain_sequence";
+const AUDITA_CHAIN_PREVIOUS_KEYWORD: &str = "audita_chain_previous";
+const AUDITA_CHAIN_CREATED_AT_KEYWORD: &str = "audita_chain_created_at";
+const RETRIEVE_PAGE_SIZE: usize = 10_000;
+const DIGEST_PAGE_SIZE: usize = 1_000;
+const LINKS_PAGE_SIZE: usize = 1_000;
 
 #[derive(Clone)]
 pub struct ElasticsearchStorageRepository {
@@ -21,13 +34,82 @@ pub struct ElasticsearchStorageRepository {
     hasher: Arc<dyn Hasher>,
 }
 
+pub fn connect(url: String, username: String, password: String) -> Result<Elasticsearch> {
+    let pool = SingleNodeConnectionPool::new(url.parse()?);
+    let credentials = Credentials::Basic(username, password);
+    let transport = TransportBuilder::new(pool).auth(credentials).cert_validation(CertificateValidation::None).build()?;
+    Ok(Elasticsearch::new(transport))
+}
+
+/// What audita records alongside every stored document about how its batch was hashed.
+struct Metadata {
+    hash: HashSpec,
+    link: Option<ChainLink>,
+}
+
+/// Removes the fields audita adds to every stored document. Documents stored before a
+/// field was recorded keep the legacy defaults: no link and the default hash spec.
+fn strip_metadata(source: &mut Map<String, Value>) -> Result<Metadata> {
+    source.remove(AUDITA_ID_KEYWORD);
+    source.remove(AUDITA_ORD_KEYWORD);
+
+    let mut hash = HashSpec::default();
+    if let Some(value) = source.remove(AUDITA_HASH_ALGORITHM_KEYWORD) {
+        hash.algorithm = serde_json::from_value(value)?;
+    }
+    if let Some(value) = source.remove(AUDITA_HASH_SCHEME_KEYWORD) {
+        hash.scheme = serde_json::from_value(value)?;
+    }
+    if let Some(value) = source.remove(AUDITA_CANONICALIZATION_KEYWORD) {
+        hash.canonicalization = serde_json::from_value(value)?;
+    }
+
+    let stream = source.remove(AUDITA_CHAIN_STREAM_KEYWORD);
+    let sequence = source.remove(AUDITA_CHAIN_SEQUENCE_KEYWORD);
+    let previous = source.remove(AUDITA_CHAIN_PREVIOUS_KEYWORD);
+    let created_at = source.remove(AUDITA_CHAIN_CREATED_AT_KEYWORD);
+    let link = match (stream, sequence, created_at) {
+        (Some(stream), Some(sequence), Some(created_at)) => Some(ChainLink {
+            stream: serde_json::from_value(stream)?,
+            sequence: serde_json::from_value(sequence)?,
+            previous: match previous {
+                Some(Value::String(previous)) => {
+                    let mut digest = [0u8; 32];
+                    hex::decode_to_slice(previous, &mut digest)?;
+                    Some(digest)
+                }
+                _ => None,
+            },
+            created_at: serde_json::from_value(created_at)?,
+        }),
+        _ => None,
+    };
+
+    Ok(Metadata { hash, link })
+}
+
 impl ElasticsearchStorageRepository {
     pub fn new(url: String, username: String, password: String, indices_pattern: String, hasher: Arc<dyn Hasher>) -> Result<Self> {
-        let pool = SingleNodeConnectionPool::new(url.parse()?);
-        let credentials = Credentials::Basic(username, password);
-        let transport = TransportBuilder::new(pool).auth(credentials).cert_validation(CertificateValidation::None).build()?;
+        Ok(Self { client: connect(url, username, password)?, indices_pattern, hasher })
+    }
+
+    /// One page of a batch's documents in `audita_ord` order, starting after the
+    /// sort values of the previous page.
+    async fn page(&self, id: &str, after: Option<Vec<Value>>, size: usize) -> Result<Vec<Value>> {
+        let mut search = json!({
+            "query": { "term": { format!("{AUDITA_ID_KEYWORD}.keyword"): id } },
+            "sort": [{ AUDITA_ORD_KEYWORD: "asc" }],
+            "size": size
+        });
+        if let Some(values) = after {
+            search["search_after"] = json!(values);
+        }
 
-        Ok(Self { client: Elasticsearch::new(transport), indices_pattern, hasher })
+        let mut body = self.client.search(SearchParts::None).body(search).send().await?.json::<Value>().await?;
+        match body["hits"]["hits"].take() {
+            Value::Array(hits) => Ok(hits),
+            _ => Ok(Vec::new()),
+        }
     }
 
     fn condition_to_query(&self, cond: &Condition) -> Value {
@@ -50,13 +132,13 @@ impl ElasticsearchStorageRepository {
             Operator::BeforeDate(dt) => json!({ "range": { field: { "lt": dt.to_rfc3339() } } }),
             Operator::BetweenDate(start, end) => json!({ "range": { field: { "gte": start.to_rfc3339(), "lte": end.to_rfc3339() } } }),
         };
-        return query;
+        query
     }
 
     fn parse_query(&self, query: &Query) -> Value {
-        let and_empty = query.and.as_ref().map_or(true, |v| v.is_empty());
-        let or_empty = query.or.as_ref().map_or(true, |v| v.is_empty());
-        let not_empty = query.not.as_ref().map_or(true, |v| v.is_empty());
+        let and_empty = query.and.as_ref().is_none_or(|v| v.is_empty());
+        let or_empty = query.or.as_ref().is_none_or(|v| v.is_empty());
+        let not_empty = query.not.as_ref().is_none_or(|v| v.is_empty());
 
         if and_empty && or_empty && not_empty {
             return json!({ "match_all": {} });
@@ -97,7 +179,7 @@ impl ElasticsearchStorageRepository {
             bool_query["bool"]["must_not"] = json!(must_not);
         }
 
-        return bool_query;
+        bool_query
     }
 }
 
@@ -111,7 +193,18 @@ impl StorageRepository for ElasticsearchStorageRepository {
             let mut content = doc.clone();
             content.insert(AUDITA_ID_KEYWORD.into(), batch.id.clone().into());
             content.insert(AUDITA_ORD_KEYWORD.into(), i.into());
-            ops.push(BulkOperation::create(content.into()).index(&index).into());
+            content.insert(AUDITA_HASH_ALGORITHM_KEYWORD.into(), serde_json::to_value(batch.hash.algorithm)?);
+            content.insert(AUDITA_HASH_SCHEME_KEYWORD.into(), serde_json::to_value(batch.hash.scheme)?);
+            content.insert(AUDITA_CANONICALIZATION_KEYWORD.into(), serde_json::to_value(batch.hash.canonicalization)?);
+            if let Some(link) = &batch.link {
+                content.insert(AUDITA_CHAIN_STREAM_KEYWORD.into(), link.stream.clone().into());
+                content.insert(AUDITA_CHAIN_SEQUENCE_KEYWORD.into(), link.sequence.into());
+                if let Some(previous) = &link.previous {
+                    content.insert(AUDITA_CHAIN_PREVIOUS_KEYWORD.into(), hex::encode(previous).into());
+                }
+                content.insert(AUDITA_CHAIN_CREATED_AT_KEYWORD.into(), link.created_at.to_rfc3339_opts(SecondsFormat::Micros, true).into());
+            }
+            ops.push(BulkOperation::create(content).id(format!("{}-{}", batch.id, i)).index(&index).into());
         }
 
         let response = self.client.bulk(BulkParts::None).body(ops).send().await?;
@@ -122,55 +215,163 @@ impl StorageRepository for ElasticsearchStorageRepository {
             bail!("bulk insert request failed with status {}: {}", status, error_body);
         }
 
+        // Replayed batches hit documents that already exist; those conflicts are expected.
+        let body: Value = response.json().await?;
+        if body["errors"].as_bool().unwrap_or(false) {
+            let failed: Vec<&Value> = body["items"]
+                .as_array()
+                .map(|items| items.iter().filter(|item| !matches!(item["create"]["status"].as_u64(), Some(200..=299 | 409))).collect())
+                .unwrap_or_default();
+            if !failed.is_empty() {
+                bail!("bulk insert failed for {} documents: {}", failed.len(), json!(failed));
+            }
+        }
+
         Ok(())
     }
 
-    async fn retrieve(&self, id: &String) -> Result<Option<Batch>> {
+    async fn retrieve(&self, id: &str) -> Result<Option<Batch>> {
         let mut documents = Vec::new();
+        let mut metadata = Metadata { hash: HashSpec::default(), link: None };
         let mut after = None;
         loop {
-            let mut search = json!({
-                "query": { "term": { format!("{AUDITA_ID_KEYWORD}.keyword"): id } },
-                "sort": [{ AUDITA_ORD_KEYWORD: "asc" }],
-                "size": 10_000
-            });
-
-            if let Some(values) = &after {
-                search["search_after"] = json!(values);
-            }
-            let hits = self.client.search(SearchParts::None).body(search).send().await?.json::<Value>().await?["hits"]["hits"]
-                .as_array()
-                .cloned()
-                .unwrap_or_default();
+            let hits = self.page(id, after.take(), RETRIEVE_PAGE_SIZE).await?;
+            let Some(last) = hits.last() else { break };
+            after = last["sort"].as_array().cloned();
 
-            if hits.is_empty() {
-                break;
-            }
-            for hit in &hits {
-                if let Some(mut source) = hit["_source"].as_object().cloned() {
-                    let _ = source.remove(AUDITA_ORD_KEYWORD).as_ref().and_then(Value::as_u64).unwrap() as usize;
-                    let _ = source.remove(AUDITA_ID_KEYWORD).as_ref().and_then(Value::as_str).unwrap().to_string();
+            for mut hit in hits {
+                if let Value::Object(mut source) = hit["_source"].take() {
+                    metadata = strip_metadata(&mut source)?;
                     documents.push(source);
                 }
             }
-
-            after = hits.last().and_then(|hit| hit["sort"].as_array().cloned());
         }
         if documents.is_empty() {
             return Ok(None);
         }
-        let digest = self.hasher.digest(&documents)?;
+        let Metadata { hash, link } = metadata;
+        let content = self.hasher.digest(&documents, &hash)?;
+        let digest = match &link {
+            Some(link) => self.hasher.link(&hash, &content, link),
+            None => content,
+        };
 
-        Ok(Some(Batch { id: id.clone(), documents, digest }))
+        Ok(Some(Batch { id: id.to_string(), documents, digest, hash, link }))
+    }
+
+    async fn digest(&self, id: &str) -> Result<Option<StoredDigest>> {
+        let mut stream: Option<(Metadata, Box<dyn DigestStream>)> = None;
+        let mut count = 0;
+        let mut after = None;
+        loop {
+            let hits = self.page(id, after.take(), DIGEST_PAGE_SIZE).await?;
+            let Some(last) = hits.last() else { break };
+            after = last["sort"].as_array().cloned();
+
+            for mut hit in hits {
+                if let Value::Object(mut source) = hit["_source"].take() {
+                    let metadata = strip_metadata(&mut source)?;
+                    let (_, stream) = stream.get_or_insert_with(|| {
+                        let stream = self.hasher.stream(&metadata.hash);
+                        (metadata, stream)
+                    });
+                    stream.update(&source)?;
+                    count += 1;
+                }
+            }
+        }
+
+        let Some((Metadata { hash, link }, stream)) = stream else { return Ok(None) };
+        let content = stream.finalize();
+        let digest = match &link {
+            Some(link) => self.hasher.link(&hash, &content, link),
+            None => content,
+        };
+        Ok(Some(StoredDigest { digest, hash, count }))
+    }
+
+    async fn links(&self, stream: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<LinkedBatch>> {
+        let mut links = Vec::new();
+        let mut after: Option<Vec<Value>> = None;
+        loop {
+            // Every batch has exactly one document with `audita_ord` 0, which carries the link.
+            let mut search = json!({
+                "query": { "bool": { "filter": [
+                    { "term": { format!("{AUDITA_CHAIN_STREAM_KEYWORD}.keyword"): stream } },
+                    { "term": { AUDITA_ORD_KEYWORD: 0 } },
+                    { "range": { AUDITA_CHAIN_CREATED_AT_KEYWORD: { "gte": from.to_rfc3339(), "lte": to.to_rfc3339() } } }
+                ] } },
+                "sort": [{ AUDITA_CHAIN_SEQUENCE_KEYWORD: "asc" }, { format!("{AUDITA_ID_KEYWORD}.keyword"): "asc" }],
+                "_source": [
+                    AUDITA_ID_KEYWORD,
+                    AUDITA_CHAIN_STREAM_KEYWORD,
+                    AUDITA_CHAIN_SEQUENCE_KEYWORD,
+                    AUDITA_CHAIN_PREVIOUS_KEYWORD,
+                    AUDITA_CHAIN_CREATED_AT_KEYWORD
+                ],
+                "size": LINKS_PAGE_SIZE
+            });
+            if let Some(values) = after.take() {
+                search["search_after"] = json!(values);
+            }
+
+            let mut body = self.client.search(SearchParts::None).body(search).send().await?.json::<Value>().await?;
+            let Value::Array(hits) = body["hits"]["hits"].take() else { break };
+            let Some(last) = hits.last() else { break };
+            after = last["sort"].as_array().cloned();
+
+            for mut hit in hits {
+                if let Value::Object(mut source) = hit["_source"].take() {
+                    let Some(id) = source.get(AUDITA_ID_KEYWORD).and_then(Value::as_str).map(str::to_string) else { continue };
+                    if let Some(link) = strip_metadata(&mut source)?.link {
+                        links.push(LinkedBatch { id, link });
+                    }
+                }
+            }
+        }
+        Ok(links)
+    }
+
+    async fn link(&self, stream: &str, sequence: u64) -> Result<Option<LinkedBatch>> {
+        let search = json!({
+            "query": { "bool": { "filter": [
+                { "term": { format!("{AUDITA_CHAIN_STREAM_KEYWORD}.keyword"): stream } },
+                { "term": { AUDITA_ORD_KEYWORD: 0 } },
+                { "term": { AUDITA_CHAIN_SEQUENCE_KEYWORD: sequence } }
+            ] } },
+            "sort": [{ format!("{AUDITA_ID_KEYWORD}.keyword"): "asc" }],
+            "_source": [
+                AUDITA_ID_KEYWORD,
+                AUDITA_CHAIN_STREAM_KEYWORD,
+                AUDITA_CHAIN_SEQUENCE_KEYWORD,
+                AUDITA_CHAIN_PREVIOUS_KEYWORD,
+                AUDITA_CHAIN_CREATED_AT_KEYWORD
+            ],
+            "size": 1
+        });
+
+        let mut body = self.client.search(SearchParts::None).body(search).send().await?.json::<Value>().await?;
+        let Value::Object(mut source) = body["hits"]["hits"][0]["_source"].take() else { return Ok(None) };
+        let Some(id) = source.get(AUDITA_ID_KEYWORD).and_then(Value::as_str).map(str::to_string) else { return Ok(None) };
+        Ok(strip_metadata(&mut source)?.link.map(|link| LinkedBatch { id, link }))
     }
 
     async fn search(&self, query: &Query) -> Result<QueryResult> {
-        let query = self.parse_query(&query);
+        let sort = match &query.sort {
+            Some(sort) => {
+                let order = match sort.order {
+                    SortOrder::Asc => "asc",
+                    SortOrder::Desc => "desc",
+                };
+                json!([{ sort.field.as_str(): order }, { AUDITA_ORD_KEYWORD: "asc" }])
+            }
+            None => json!([{ AUDITA_ORD_KEYWORD: "asc" }]),
+        };
 
         let search = json!({
-            "query": query,
-            "sort": [{ AUDITA_ORD_KEYWORD: "asc" }],
-            "size": 50
+            "query": self.parse_query(query),
+            "sort": sort,
+            "size": query.size.unwrap_or(50)
         });
 
         let response = self.client.search(SearchParts::None).body(search).send().await?;
@@ -182,8 +383,8 @@ impl StorageRepository for ElasticsearchStorageRepository {
             .into_iter()
             .filter_map(|hit| {
                 let mut source = hit.get("_source")?.as_object()?.clone();
-                let _ = source.remove("audita_ord")?.as_u64()? as usize;
-                let id = source.remove("audita_id")?.as_str()?.to_string();
+                let id = source.get(AUDITA_ID_KEYWORD)?.as_str()?.to_string();
+                strip_metadata(&mut source).ok()?;
                 Some(DocumentQuery { id, source })
             })
             .collect();
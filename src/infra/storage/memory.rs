@@ -1,41 +1,175 @@
-use crate::domain::{Batch, DocumentQuery, Hasher, Query, QueryResult, StorageRepository};
-use anyhow::Result;
+use crate::domain::{
+    Batch, Condition, Document, DocumentQuery, Hasher, LinkedBatch, Operator, Query, QueryResult, SortOrder, StorageRepository,
+    StoredDigest,
+};
+use anyhow::{bail, Result};
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
 
+/// Keeps batches in process memory, for development and tests without Elasticsearch.
+/// Digests are recomputed from the held documents, as they are from Elasticsearch.
 #[derive(Clone)]
 pub struct MemoryStorageRepository {
     store: Arc<RwLock<HashMap<String, Batch>>>,
-    _hasher: Arc<dyn Hasher>,
+    hasher: Arc<dyn Hasher>,
 }
 
 impl MemoryStorageRepository {
     pub fn new(hasher: Arc<dyn Hasher>) -> Self {
-        Self { store: Arc::new(RwLock::new(HashMap::new())), _hasher: hasher }
+        Self { store: Arc::new(RwLock::new(HashMap::new())), hasher }
+    }
+
+    fn recompute(&self, batch: &Batch) -> Result<[u8; 32]> {
+        let content = self.hasher.digest(&batch.documents, &batch.hash)?;
+        Ok(match &batch.link {
+            Some(link) => self.hasher.link(&batch.hash, &content, link),
+            None => content,
+        })
     }
 }
 
 #[async_trait]
 impl StorageRepository for MemoryStorageRepository {
     async fn store(&self, batch: &Batch) -> Result<()> {
-        self.store.write().await.insert(batch.id.clone(), batch.clone());
+        // Replayed batches keep the documents stored first, like Elasticsearch create operations.
+        self.store.write().await.entry(batch.id.clone()).or_insert_with(|| batch.clone());
         Ok(())
     }
 
-    async fn retrieve(&self, id: &String) -> Result<Option<Batch>> {
-        Ok(self.store.read().await.get(id).cloned())
+    async fn retrieve(&self, id: &str) -> Result<Option<Batch>> {
+        let Some(mut batch) = self.store.read().await.get(id).cloned() else { return Ok(None) };
+        batch.digest = self.recompute(&batch)?;
+        Ok(Some(batch))
+    }
+
+    async fn digest(&self, id: &str) -> Result<Option<StoredDigest>> {
+        let store = self.store.read().await;
+        let Some(batch) = store.get(id) else { return Ok(None) };
+        Ok(Some(StoredDigest { digest: self.recompute(batch)?, hash: batch.hash, count: batch.documents.len() }))
     }
 
-    async fn search(&self, _query: &Query) -> Result<QueryResult> {
+    async fn links(&self, stream: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<LinkedBatch>> {
         let store = self.store.read().await;
-        let mut result = Vec::new();
+        let mut links: Vec<LinkedBatch> = store
+            .values()
+            .filter_map(|batch| Some(LinkedBatch { id: batch.id.clone(), link: batch.link.clone()? }))
+            .filter(|linked| linked.link.stream == stream && linked.link.created_at >= from && linked.link.created_at <= to)
+            .collect();
+        links.sort_by_key(|linked| linked.link.sequence);
+        Ok(links)
+    }
+
+    async fn link(&self, stream: &str, sequence: u64) -> Result<Option<LinkedBatch>> {
+        let store = self.store.read().await;
+        Ok(store
+            .values()
+            .filter_map(|batch| Some(LinkedBatch { id: batch.id.clone(), link: batch.link.clone()? }))
+            .find(|linked| linked.link.stream == stream && linked.link.sequence == sequence))
+    }
+
+    async fn search(&self, query: &Query) -> Result<QueryResult> {
+        let store = self.store.read().await;
+        let mut hits = Vec::new();
         for batch in store.values() {
-            for doc in &batch.documents {
-                result.push(DocumentQuery { id: batch.id.clone(), source: doc.clone() });
+            for (ord, doc) in batch.documents.iter().enumerate() {
+                if matches(doc, query)? {
+                    hits.push((ord, DocumentQuery { id: batch.id.clone(), source: doc.clone() }));
+                }
+            }
+        }
+
+        hits.sort_by(|(a_ord, a), (b_ord, b)| {
+            let by_field = query
+                .sort
+                .as_ref()
+                .map_or(Ordering::Equal, |sort| compare(field(&a.source, &sort.field), field(&b.source, &sort.field), sort.order));
+            by_field.then(a_ord.cmp(b_ord))
+        });
+        hits.truncate(query.size.unwrap_or(50));
+        Ok(hits.into_iter().map(|(_, hit)| hit).collect())
+    }
+}
+
+fn matches(doc: &Document, query: &Query) -> Result<bool> {
+    if let Some(conditions) = &query.and {
+        for condition in conditions {
+            if !condition_matches(doc, condition)? {
+                return Ok(false);
             }
         }
-        Ok(result)
+    }
+    if let Some(conditions) = query.or.as_ref().filter(|conditions| !conditions.is_empty()) {
+        let mut any = false;
+        for condition in conditions {
+            any |= condition_matches(doc, condition)?;
+        }
+        if !any {
+            return Ok(false);
+        }
+    }
+    if let Some(conditions) = &query.not {
+        for condition in conditions {
+            if condition_matches(doc, condition)? {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn condition_matches(doc: &Document, condition: &Condition) -> Result<bool> {
+    let value = field(doc, &condition.field);
+    let text = value.and_then(Value::as_str);
+    let int = value.and_then(Value::as_i64);
+    let date = text.and_then(|text| DateTime::parse_from_rfc3339(text).ok()).map(|date| date.with_timezone(&Utc));
+
+    Ok(match &condition.op {
+        Operator::EqString(val) => text == Some(val.as_str()),
+        Operator::NeqString(val) => text != Some(val.as_str()),
+        Operator::Contains(val) => text.is_some_and(|text| text.contains(val.as_str())),
+        Operator::StartsWith(val) => text.is_some_and(|text| text.starts_with(val.as_str())),
+        Operator::EndsWith(val) => text.is_some_and(|text| text.ends_with(val.as_str())),
+        Operator::Regex(_) => bail!("regex conditions are not supported by the memory storage backend"),
+        Operator::EqInt(val) => int == Some(*val),
+        Operator::NeqInt(val) => int != Some(*val),
+        Operator::GtInt(val) => int.is_some_and(|int| int > *val),
+        Operator::LtInt(val) => int.is_some_and(|int| int < *val),
+        Operator::BetweenInt(min, max) => int.is_some_and(|int| int >= *min && int <= *max),
+        Operator::EqDate(dt) => date == Some(*dt),
+        Operator::NeqDate(dt) => date != Some(*dt),
+        Operator::AfterDate(dt) => date.is_some_and(|date| date > *dt),
+        Operator::BeforeDate(dt) => date.is_some_and(|date| date < *dt),
+        Operator::BetweenDate(start, end) => date.is_some_and(|date| date >= *start && date <= *end),
+    })
+}
+
+/// Looks a field up by its dotted path, as Elasticsearch addresses object fields.
+fn field<'a>(doc: &'a Document, path: &str) -> Option<&'a Value> {
+    if let Some(value) = doc.get(path) {
+        return Some(value);
+    }
+    let mut parts = path.split('.');
+    let mut value = doc.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn compare(a: Option<&Value>, b: Option<&Value>, order: SortOrder) -> Ordering {
+    let ordering = match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        // Documents missing the field sort last in either order, as in Elasticsearch.
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    };
+    match (order, a.is_some() && b.is_some()) {
+        (SortOrder::Desc, true) => ordering.reverse(),
+        _ => ordering,
     }
 }
@@ -22,14 +22,18 @@ async fn main() {
         let ctx = ctx.clone();
         tokio::spawn(tasks::worker::run(ctx));
     }
-    for _ in 0..100 {
-        let ctx = ctx.clone();
-        tokio::spawn(tasks::signer::run(ctx));
-    }
+    tokio::spawn(tasks::signer::run(ctx.clone()));
     for _ in 0..100 {
         let ctx = ctx.clone();
         tokio::spawn(tasks::storage::run(ctx));
     }
+    tokio::spawn(tasks::log::run(ctx.clone()));
+    for addr in ctx.config.syslog.udp.clone() {
+        tokio::spawn(tasks::syslog::udp(ctx.clone(), addr));
+    }
+    for addr in ctx.config.syslog.tcp.clone() {
+        tokio::spawn(tasks::syslog::tcp(ctx.clone(), addr));
+    }
 
     info!("Starting HTTP server...");
     if let Err(err) = server::run(ctx.clone()).await {
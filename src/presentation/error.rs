@@ -1,5 +1,6 @@
+use crate::domain::ChannelError;
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -8,21 +9,41 @@ use tracing::error;
 
 #[derive(Debug)]
 pub enum AppError {
-    // BadRequest(String),
+    BadRequest(String),
     NotFound(String),
     // Unauthorized(String),
+    PayloadTooLarge(String),
+    TooManyRequests { message: String, retry_after: u64 },
+    ServiceUnavailable { message: String, retry_after: u64 },
     Internal(anyhow::Error),
 }
 
+impl AppError {
+    /// Maps a rejected enqueue to the status a log shipper should back off on.
+    pub fn from_channel(err: ChannelError, retry_after: u64) -> Self {
+        match err {
+            ChannelError::Full => AppError::TooManyRequests { message: "Ingestion queue is full, retry later".into(), retry_after },
+            ChannelError::Closed => AppError::ServiceUnavailable { message: "Ingestion pipeline is shutting down".into(), retry_after },
+            ChannelError::TooLarge { capacity } => {
+                AppError::PayloadTooLarge(format!("Request holds more documents than the ingestion queue size of {}", capacity))
+            }
+            ChannelError::Failed(err) => AppError::Internal(err),
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            // AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            // AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+        let (status, message, retry_after) = match self {
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg, None),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg, None),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg, None),
+            // AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg, None),
+            AppError::TooManyRequests { message, retry_after } => (StatusCode::TOO_MANY_REQUESTS, message, Some(retry_after)),
+            AppError::ServiceUnavailable { message, retry_after } => (StatusCode::SERVICE_UNAVAILABLE, message, Some(retry_after)),
             AppError::Internal(err) => {
                 error!(error = %err, "Internal server error occurred");
-                (StatusCode::INTERNAL_SERVER_ERROR, format!("Internal server error: {}", err))
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("Internal server error: {}", err), None)
             }
         };
 
@@ -32,7 +53,12 @@ impl IntoResponse for AppError {
                 "status": status.as_u16()
             }
         }));
-        (status, body).into_response()
+
+        let mut response = (status, body).into_response();
+        if let Some(retry_after) = retry_after {
+            response.headers_mut().insert(RETRY_AFTER, retry_after.into());
+        }
+        response
     }
 }
 
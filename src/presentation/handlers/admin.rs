@@ -0,0 +1,63 @@
+use crate::{
+    context::Context,
+    domain::{DeadLetter, Stage},
+    presentation::error::{AppError, HttpResult},
+    tasks::retry,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct DeadLetterSummary {
+    id: String,
+    stage: Stage,
+    attempts: u32,
+    last_error: String,
+    failed_at: DateTime<Utc>,
+    documents: usize,
+}
+
+#[derive(Serialize)]
+pub struct ListDeadLettersResponse {
+    dead_letters: Vec<DeadLetterSummary>,
+}
+
+pub async fn list_dead_letters(State(ctx): State<Context>) -> HttpResult<Json<ListDeadLettersResponse>> {
+    let dead_letters = ctx
+        .dead_letters
+        .list()
+        .await?
+        .into_iter()
+        .map(|letter| DeadLetterSummary {
+            id: letter.batch.id,
+            stage: letter.stage,
+            attempts: letter.attempts,
+            last_error: letter.last_error,
+            failed_at: letter.failed_at,
+            documents: letter.batch.documents.len(),
+        })
+        .collect();
+    Ok(Json(ListDeadLettersResponse { dead_letters }))
+}
+
+pub async fn get_dead_letter(State(ctx): State<Context>, Path((stage, id)): Path<(Stage, String)>) -> HttpResult<Json<DeadLetter>> {
+    match ctx.dead_letters.get(stage, &id).await? {
+        Some(letter) => Ok(Json(letter)),
+        None => Err(AppError::NotFound("No dead letter found for the given stage and batch_id".into())),
+    }
+}
+
+pub async fn redrive_dead_letter(State(ctx): State<Context>, Path((stage, id)): Path<(Stage, String)>) -> HttpResult<StatusCode> {
+    match ctx.dead_letters.get(stage, &id).await? {
+        Some(letter) => {
+            retry::redrive(&ctx, letter).await?;
+            Ok(StatusCode::ACCEPTED)
+        }
+        None => Err(AppError::NotFound("No dead letter found for the given stage and batch_id".into())),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::{
+    context::Context,
+    domain::BatchStatus,
+    presentation::error::{AppError, HttpResult},
+};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+pub async fn get_batch_status(State(ctx): State<Context>, Path(id): Path<String>) -> HttpResult<Json<BatchStatus>> {
+    match ctx.status.get(&id).await? {
+        Some(status) => Ok(Json(status)),
+        None => Err(AppError::NotFound("No status found for the given batch_id".into())),
+    }
+}
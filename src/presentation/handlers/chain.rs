@@ -0,0 +1,107 @@
+use crate::{
+    context::Context,
+    domain::{ChainIssue, ChainIssueKind, ChainReport},
+    presentation::error::HttpResult,
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct VerifyChainRequest {
+    stream: Option<String>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+}
+
+/// Walks the batches of a stream created between `from` and `to` in sequence order.
+/// Each batch must follow its predecessor without gaps, point at the predecessor's
+/// digest as recomputed from storage, and match the digest anchored by the signer.
+/// The first batch is checked against its predecessor, looked up outside the window.
+/// When the window reaches this instance's chain head, the last batch must be it.
+pub async fn verify_chain(State(ctx): State<Context>, Query(request): Query<VerifyChainRequest>) -> HttpResult<Json<ChainReport>> {
+    let stream = request.stream.unwrap_or_else(|| ctx.chain.stream().to_string());
+    let links = ctx.storage.links(&stream, request.from, request.to).await?;
+
+    let mut issues = Vec::new();
+    let mut issue = |kind, batch_id: Option<&str>, sequence, detail: String| {
+        issues.push(ChainIssue { kind, batch_id: batch_id.map(str::to_string), sequence, detail })
+    };
+
+    // The first batch in the window is checked against its predecessor too, which
+    // lies outside the window.
+    let mut previous: Option<(u64, Option<[u8; 32]>, DateTime<Utc>)> = None;
+    if let Some(first) = links.first() {
+        let (id, link) = (first.id.as_str(), &first.link);
+        match link.sequence.checked_sub(1) {
+            None if link.previous.is_some() => {
+                issue(ChainIssueKind::BrokenLink, Some(id), link.sequence, "first batch points at a predecessor".into())
+            }
+            None => {}
+            Some(sequence) => match ctx.storage.link(&stream, sequence).await? {
+                Some(predecessor) => {
+                    let stored = ctx.storage.digest(&predecessor.id).await?.map(|stored| stored.digest);
+                    previous = Some((sequence, stored, predecessor.link.created_at));
+                }
+                None => issue(ChainIssueKind::Missing, None, sequence, format!("predecessor {} of the first batch is missing", sequence)),
+            },
+        }
+    }
+    for linked in &links {
+        let (id, link) = (linked.id.as_str(), &linked.link);
+        let stored = ctx.storage.digest(id).await?.map(|stored| stored.digest);
+        let anchored = ctx.signer.digest(id).await?;
+        if stored.is_none() || stored != anchored {
+            let detail = format!(
+                "storage digest {} does not match anchored digest {}",
+                stored.map(hex::encode).unwrap_or_else(|| "-".into()),
+                anchored.map(hex::encode).unwrap_or_else(|| "-".into())
+            );
+            issue(ChainIssueKind::DigestMismatch, Some(id), link.sequence, detail);
+        }
+
+        if let Some((sequence, digest, created_at)) = previous {
+            if link.sequence == sequence {
+                issue(ChainIssueKind::Duplicate, Some(id), link.sequence, format!("sequence {} appears more than once", sequence));
+            } else if link.sequence > sequence + 1 {
+                let detail = format!("sequences {} to {} are missing", sequence + 1, link.sequence - 1);
+                issue(ChainIssueKind::Missing, None, sequence + 1, detail);
+            } else if link.previous != digest {
+                issue(ChainIssueKind::BrokenLink, Some(id), link.sequence, format!("does not point at batch {}", sequence));
+            }
+            if link.created_at < created_at {
+                issue(ChainIssueKind::Reordered, Some(id), link.sequence, format!("created before batch {}", sequence));
+            }
+        }
+        previous = Some((link.sequence, stored, link.created_at));
+    }
+
+    if stream == ctx.chain.stream() {
+        if let Some(head) = ctx.chain.head().await? {
+            if head.created_at <= request.to && head.created_at >= request.from {
+                match previous {
+                    Some((sequence, _, _)) if sequence >= head.sequence => {}
+                    last => {
+                        let first = last.map_or(0, |(sequence, _, _)| sequence + 1);
+                        let detail = format!("chain head is at sequence {}, storage ends before it", head.sequence);
+                        issue(ChainIssueKind::Truncated, None, first, detail);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(Json(ChainReport {
+        stream,
+        from: request.from,
+        to: request.to,
+        batches: links.len(),
+        first_sequence: links.first().map(|linked| linked.link.sequence),
+        last_sequence: links.last().map(|linked| linked.link.sequence),
+        verified: issues.is_empty(),
+        issues,
+    }))
+}
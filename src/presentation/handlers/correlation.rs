@@ -0,0 +1,150 @@
+use crate::{
+    context::Context,
+    domain::{Attribution, Batch, Condition, DocumentQuery, Hop, Operator, Query as SearchQuery, Sort, SortOrder, Verification},
+    presentation::error::HttpResult,
+};
+use anyhow::Result;
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+pub struct NatAttributionRequest {
+    ip: String,
+    port: String,
+    timestamp: DateTime<Utc>,
+}
+
+/// Resolves who was behind a public `ip:port` at a given time: the nearest firewall
+/// NAT log, then the DHCP lease of its internal IP, then the RADIUS session of that
+/// lease's MAC. Every hop is checked against the digest anchored for its batch.
+pub async fn nat_attribution(State(ctx): State<Context>, Query(request): Query<NatAttributionRequest>) -> HttpResult<Json<Attribution>> {
+    let config = &ctx.config.correlation;
+    let mut verifier = Verifier::new(&ctx);
+
+    let conditions = vec![
+        eq(&config.firewall.mapped_ip_field, request.ip),
+        eq(&config.firewall.mapped_port_field, request.port),
+        eq(&config.type_field, config.firewall.kind.clone()),
+    ];
+    let Some(firewall) = nearest(&ctx, conditions, request.timestamp, config.firewall.before_secs, config.firewall.after_secs).await?
+    else {
+        return Ok(Json(Attribution::new(None, None, None)));
+    };
+    let at = timestamp(&firewall, &config.time_field).unwrap_or(request.timestamp);
+    let ip = field(&firewall, &config.firewall.ip_field);
+    let firewall = verifier.hop(firewall).await?;
+
+    let dhcp = match ip {
+        Some(ip) => {
+            let conditions = vec![eq(&config.dhcp.ip_field, ip), eq(&config.type_field, config.dhcp.kind.clone())];
+            nearest(&ctx, conditions, at, config.dhcp.before_secs, config.dhcp.after_secs).await?
+        }
+        None => None,
+    };
+    let Some(dhcp) = dhcp else {
+        return Ok(Json(Attribution::new(Some(firewall), None, None)));
+    };
+    let mac = field(&dhcp, &config.dhcp.mac_field);
+    let dhcp = verifier.hop(dhcp).await?;
+
+    let radius = match mac {
+        Some(mac) => {
+            let mac = mac.replace([':', '-'], &config.radius.mac_separator);
+            let conditions = vec![eq(&config.radius.mac_field, mac), eq(&config.type_field, config.radius.kind.clone())];
+            nearest(&ctx, conditions, at, config.radius.before_secs, config.radius.after_secs).await?
+        }
+        None => None,
+    };
+    let radius = match radius {
+        Some(radius) => Some(verifier.hop(radius).await?),
+        None => None,
+    };
+
+    Ok(Json(Attribution::new(Some(firewall), Some(dhcp), radius)))
+}
+
+fn eq(field: &str, value: String) -> Condition {
+    Condition { field: field.to_string(), op: Operator::EqString(value) }
+}
+
+fn field(doc: &DocumentQuery, name: &str) -> Option<String> {
+    match doc.source.get(name)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+fn timestamp(doc: &DocumentQuery, field: &str) -> Option<DateTime<Utc>> {
+    let value = doc.source.get(field)?.as_str()?;
+    DateTime::parse_from_rfc3339(value).ok().map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Finds the document closest to `at` within `[at - before, at + after]` by asking
+/// storage for the latest match before `at` and the earliest one after it.
+async fn nearest(
+    ctx: &Context, conditions: Vec<Condition>, at: DateTime<Utc>, before_secs: i64, after_secs: i64,
+) -> Result<Option<DocumentQuery>> {
+    let windows = [
+        (at - TimeDelta::seconds(before_secs), at, SortOrder::Desc, before_secs),
+        (at, at + TimeDelta::seconds(after_secs), SortOrder::Asc, after_secs),
+    ];
+
+    let time_field = &ctx.config.correlation.time_field;
+    let mut closest: Option<(TimeDelta, DocumentQuery)> = None;
+    for (start, end, order, span) in windows {
+        if span <= 0 {
+            continue;
+        }
+        let mut and = conditions.clone();
+        and.push(Condition { field: time_field.clone(), op: Operator::BetweenDate(start, end) });
+        let query =
+            SearchQuery { and: Some(and), sort: Some(Sort { field: time_field.clone(), order }), size: Some(1), ..Default::default() };
+
+        for doc in ctx.storage.search(&query).await? {
+            let Some(ts) = timestamp(&doc, time_field) else { continue };
+            let distance = (ts - at).abs();
+            if closest.as_ref().is_none_or(|(best, _)| distance < *best) {
+                closest = Some((distance, doc));
+            }
+        }
+    }
+    Ok(closest.map(|(_, doc)| doc))
+}
+
+/// Checks hops against storage and signer, once per batch.
+struct Verifier<'a> {
+    ctx: &'a Context,
+    digests: HashMap<String, (Option<Batch>, Option<[u8; 32]>)>,
+}
+
+impl<'a> Verifier<'a> {
+    fn new(ctx: &'a Context) -> Self {
+        Self { ctx, digests: HashMap::new() }
+    }
+
+    async fn hop(&mut self, doc: DocumentQuery) -> Result<Hop> {
+        if !self.digests.contains_key(&doc.id) {
+            let batch = self.ctx.storage.retrieve(&doc.id).await?;
+            let anchored = self.ctx.signer.digest(&doc.id).await?;
+            self.digests.insert(doc.id.clone(), (batch, anchored));
+        }
+        let (batch, anchored) = &self.digests[&doc.id];
+
+        let included = batch.as_ref().is_some_and(|batch| batch.documents.contains(&doc.source));
+        let stored = batch.as_ref().map(|batch| batch.digest);
+        let verification = Verification {
+            included,
+            storage_digest: stored.map(hex::encode),
+            signer_digest: anchored.map(hex::encode),
+            verified: included && stored.is_some() && stored == *anchored,
+        };
+        Ok(Hop { batch_id: doc.id, document: doc.source, verification })
+    }
+}
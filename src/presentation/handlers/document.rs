@@ -1,13 +1,80 @@
-use crate::{context::Context, domain::Document, presentation::error::HttpResult};
+use crate::{
+    context::Context,
+    domain::Document,
+    presentation::error::{AppError, HttpResult},
+};
 use axum::{extract::State, Json};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize)]
 pub struct SubmitDocumentRequest(Document);
 
 pub async fn submit_document(State(ctx): State<Context>, Json(payload): Json<SubmitDocumentRequest>) -> HttpResult<()> {
-    ctx.prom.docs_total.inc();
-    ctx.prom.worker_queue_size.inc();
-    ctx.pipeline.worker.send(payload.0).await;
+    ctx.ingest(payload.0).await.map_err(|err| AppError::from_channel(err, ctx.config.ingest.retry_after_secs))?;
     Ok(())
 }
+
+#[derive(Serialize)]
+pub struct BulkError {
+    line: usize,
+    error: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct SubmitBulkResponse {
+    accepted: usize,
+    rejected: usize,
+    errors: Vec<BulkError>,
+}
+
+/// Accepts either NDJSON (one document per line) or a JSON array of documents.
+/// For arrays, `line` in the reported errors is the 1-based position in the array.
+/// Lines that parse are enqueued all together or, when the queue has no room for
+/// them, the whole request is refused so it can be retried as is.
+pub async fn submit_bulk(State(ctx): State<Context>, body: String) -> HttpResult<Json<SubmitBulkResponse>> {
+    let mut response = SubmitBulkResponse::default();
+    let mut documents = Vec::new();
+    for (line, entry) in parse_bulk(&body)? {
+        match entry {
+            Ok(document) => documents.push(document),
+            Err(error) => response.reject(line, error),
+        }
+    }
+
+    let count = documents.len();
+    if count > 0 {
+        ctx.ingest_all(documents).await.map_err(|err| AppError::from_channel(err, ctx.config.ingest.retry_after_secs))?;
+    }
+    response.accepted = count;
+    Ok(Json(response))
+}
+
+impl SubmitBulkResponse {
+    fn reject(&mut self, line: usize, error: String) {
+        self.rejected += 1;
+        self.errors.push(BulkError { line, error });
+    }
+}
+
+fn parse_bulk(body: &str) -> HttpResult<Vec<(usize, Result<Document, String>)>> {
+    if body.trim_start().starts_with('[') {
+        let values: Vec<Value> = serde_json::from_str(body).map_err(|err| AppError::BadRequest(format!("Invalid JSON array: {}", err)))?;
+        return Ok(values.into_iter().enumerate().map(|(i, value)| (i + 1, into_document(value))).collect());
+    }
+
+    let entries = body
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, serde_json::from_str(line).map_err(|err| err.to_string()).and_then(into_document)))
+        .collect();
+    Ok(entries)
+}
+
+pub fn into_document(value: Value) -> Result<Document, String> {
+    match value {
+        Value::Object(document) => Ok(document),
+        other => Err(format!("expected a JSON object, found `{}`", other)),
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{context::Context, domain::ChannelError, presentation::handlers::document::into_document};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Map, Value};
+use std::time::Instant;
+
+const VERSION: &str = "8.16.0";
+const DEFAULT_INDEX: &str = "audita";
+
+/// Elasticsearch clients (Filebeat, Logstash, Beats libraries) refuse to talk to a
+/// server that does not identify itself as Elasticsearch.
+pub async fn product_header(mut response: Response) -> Response {
+    response.headers_mut().insert("x-elastic-product", HeaderValue::from_static("Elasticsearch"));
+    response
+}
+
+pub async fn info() -> Json<Value> {
+    Json(json!({
+        "name": "audita",
+        "cluster_name": "audita",
+        "cluster_uuid": "audita",
+        "version": {
+            "number": VERSION,
+            "build_flavor": "default",
+            "build_type": "docker",
+            "build_hash": "audita",
+            "build_date": "2024-01-01T00:00:00.000Z",
+            "build_snapshot": false,
+            "lucene_version": "9.12.0",
+            "minimum_wire_compatibility_version": "7.17.0",
+            "minimum_index_compatibility_version": "7.0.0"
+        },
+        "tagline": "You Know, for Search"
+    }))
+}
+
+pub async fn license() -> Json<Value> {
+    Json(json!({
+        "license": {
+            "status": "active",
+            "uid": "audita",
+            "type": "basic",
+            "issue_date": "2024-01-01T00:00:00.000Z",
+            "issue_date_in_millis": 1704067200000u64,
+            "max_nodes": 1000,
+            "max_resource_units": null,
+            "issued_to": "audita",
+            "issuer": "audita",
+            "start_date_in_millis": -1
+        }
+    }))
+}
+
+pub async fn bulk(State(ctx): State<Context>, body: String) -> Response {
+    process_bulk(&ctx, DEFAULT_INDEX, &body).await
+}
+
+pub async fn bulk_index(State(ctx): State<Context>, Path(index): Path<String>, body: String) -> Response {
+    process_bulk(&ctx, &index, &body).await
+}
+
+/// Parses bulk action/source line pairs. `index` and `create` actions feed their
+/// source into the pipeline; `update` and `delete` are rejected per item because
+/// audited documents are append-only. A full queue is reported as a per-item 429,
+/// which shippers retry on their own.
+async fn process_bulk(ctx: &Context, default_index: &str, body: &str) -> Response {
+    let start = Instant::now();
+    let mut items = Vec::new();
+    let mut errors = false;
+    let mut lines = body.lines().filter(|line| !line.trim().is_empty());
+
+    while let Some(line) = lines.next() {
+        let (op, meta) = match parse_action(line) {
+            Ok(action) => action,
+            Err(reason) => return bad_request(reason),
+        };
+        let index = meta.get("_index").and_then(Value::as_str).unwrap_or(default_index).to_string();
+        let id = meta.get("_id").and_then(Value::as_str).map(str::to_string).unwrap_or_else(|| ctx.uuid.generate());
+
+        let item = match op.as_str() {
+            "index" | "create" => {
+                let Some(source) = lines.next() else {
+                    return bad_request(format!("missing source line for `{}` action", op));
+                };
+                match serde_json::from_str(source).map_err(|err| err.to_string()).and_then(into_document) {
+                    Ok(document) => match ctx.ingest(document).await {
+                        Ok(()) => created(&index, &id),
+                        Err(err @ (ChannelError::Full | ChannelError::Closed)) => {
+                            failed(&index, &id, 429, "es_rejected_execution_exception", err.to_string())
+                        }
+                        Err(err) => failed(&index, &id, 500, "exception", err.to_string()),
+                    },
+                    Err(reason) => failed(&index, &id, 400, "document_parsing_exception", reason),
+                }
+            }
+            "update" => {
+                let _ = lines.next();
+                failed(&index, &id, 400, "illegal_argument_exception", "audita documents are append-only".into())
+            }
+            "delete" => failed(&index, &id, 400, "illegal_argument_exception", "audita documents are append-only".into()),
+            other => return bad_request(format!("unknown bulk action `{}`", other)),
+        };
+
+        errors |= item["status"].as_u64() != Some(201);
+        items.push(json!({ op: item }));
+    }
+
+    let took = start.elapsed().as_millis() as u64;
+    Json(json!({ "took": took, "errors": errors, "items": items })).into_response()
+}
+
+fn parse_action(line: &str) -> Result<(String, Map<String, Value>), String> {
+    let value: Value = serde_json::from_str(line).map_err(|err| format!("malformed action line: {}", err))?;
+    let action = value.as_object().filter(|action| action.len() == 1).ok_or("action line must be an object with a single key")?;
+    let (op, meta) = action.iter().next().ok_or("empty action line")?;
+    Ok((op.clone(), meta.as_object().cloned().unwrap_or_default()))
+}
+
+fn created(index: &str, id: &str) -> Value {
+    json!({
+        "_index": index,
+        "_id": id,
+        "_version": 1,
+        "result": "created",
+        "_shards": { "total": 1, "successful": 1, "failed": 0 },
+        "_seq_no": 0,
+        "_primary_term": 1,
+        "status": 201
+    })
+}
+
+fn failed(index: &str, id: &str, status: u16, kind: &str, reason: String) -> Value {
+    json!({
+        "_index": index,
+        "_id": id,
+        "status": status,
+        "error": { "type": kind, "reason": reason }
+    })
+}
+
+fn bad_request(reason: String) -> Response {
+    let body = json!({
+        "error": {
+            "root_cause": [{ "type": "illegal_argument_exception", "reason": reason }],
+            "type": "illegal_argument_exception",
+            "reason": reason
+        },
+        "status": 400
+    });
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
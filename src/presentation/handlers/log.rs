@@ -0,0 +1,149 @@
+use crate::{
+    context::Context,
+    domain::{Document, DocumentProof, HashSpec, InclusionProof, TreeHead},
+    presentation::error::{AppError, HttpResult},
+};
+use anyhow::Context as AnyhowContext;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct TreeHeadResponse {
+    tree_size: u64,
+    root: String,
+    timestamp: DateTime<Utc>,
+    digest: String,
+    hash: HashSpec,
+    anchored: Option<String>,
+    verified: bool,
+}
+
+/// Latest anchored tree head of the transparency log.
+pub async fn get_latest_tree_head(State(ctx): State<Context>) -> HttpResult<Json<TreeHeadResponse>> {
+    tree_head(&ctx, None).await
+}
+
+/// Anchored tree head of the given size, as needed to check a consistency proof.
+pub async fn get_tree_head(State(ctx): State<Context>, Path(tree_size): Path<u64>) -> HttpResult<Json<TreeHeadResponse>> {
+    tree_head(&ctx, Some(tree_size)).await
+}
+
+async fn tree_head(ctx: &Context, tree_size: Option<u64>) -> HttpResult<Json<TreeHeadResponse>> {
+    let head = find_head(ctx, tree_size).await?;
+    let anchored = ctx.signer.digest(&head.batch_id()).await?;
+    Ok(Json(TreeHeadResponse {
+        tree_size: head.tree_size,
+        root: hex::encode(head.root),
+        timestamp: head.timestamp,
+        digest: hex::encode(head.digest),
+        hash: ctx.log.spec(),
+        anchored: anchored.map(hex::encode),
+        verified: anchored == Some(head.digest),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct InclusionRequest {
+    tree_size: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct InclusionResponse {
+    id: String,
+    ord: u64,
+    document: Document,
+    index: u64,
+    tree_size: u64,
+    leaf: String,
+    path: Vec<String>,
+    root: String,
+    verified: bool,
+}
+
+/// Inclusion proof of one stored document in an anchored tree head of the log, the
+/// latest one unless `tree_size` names another.
+pub async fn get_inclusion_proof(
+    State(ctx): State<Context>, Path((id, ord)): Path<(String, u64)>, Query(request): Query<InclusionRequest>,
+) -> HttpResult<Json<InclusionResponse>> {
+    let Some(entry) = ctx.log.entry(&id).await? else {
+        return Err(AppError::NotFound("No log entry found for the given batch_id".into()));
+    };
+    if ord >= entry.count {
+        return Err(AppError::NotFound("No document found for the given audita_ord".into()));
+    }
+    let head = find_head(&ctx, request.tree_size).await?;
+    let index = entry.start + ord;
+    if index >= head.tree_size {
+        return Err(AppError::BadRequest(format!("The document was logged after the tree head of size {}", head.tree_size)));
+    }
+
+    let batch = ctx.storage.retrieve(&id).await.context("An error occurrued when retrieving data from storage")?;
+    let Some(document) = batch.and_then(|batch| batch.documents.into_iter().nth(ord as usize)) else {
+        return Err(AppError::NotFound("No records found for the given batch_id".into()));
+    };
+    let leaf = ctx.log.leaf(index).await?.context("Logged leaf is missing")?;
+    let path = ctx.log.inclusion(index, head.tree_size).await?;
+
+    let encoded = path.iter().map(hex::encode).collect();
+    let proof = InclusionProof { index: index as usize, tree_size: head.tree_size as usize, leaf, path, root: head.root };
+    let verified = ctx.hasher.verify(&document, &DocumentProof::Merkle(proof), &ctx.log.spec())?;
+
+    Ok(Json(InclusionResponse {
+        id,
+        ord,
+        document,
+        index,
+        tree_size: head.tree_size,
+        leaf: hex::encode(leaf),
+        path: encoded,
+        root: hex::encode(head.root),
+        verified,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct ConsistencyRequest {
+    first: u64,
+    second: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct ConsistencyResponse {
+    first: u64,
+    second: u64,
+    first_root: String,
+    second_root: String,
+    path: Vec<String>,
+}
+
+/// Consistency proof (RFC 6962) that the tree of `first` leaves is a prefix of the
+/// tree of `second` leaves, the latest anchored tree head unless given.
+pub async fn get_consistency_proof(
+    State(ctx): State<Context>, Query(request): Query<ConsistencyRequest>,
+) -> HttpResult<Json<ConsistencyResponse>> {
+    let second = match request.second {
+        Some(second) => second,
+        None => find_head(&ctx, None).await?.tree_size,
+    };
+    let size = ctx.log.size().await?;
+    if request.first == 0 || request.first > second || second > size {
+        return Err(AppError::BadRequest(format!("Tree sizes must satisfy 0 < first <= second <= {}", size)));
+    }
+
+    let path = ctx.log.consistency(request.first, second).await?;
+    Ok(Json(ConsistencyResponse {
+        first: request.first,
+        second,
+        first_root: hex::encode(ctx.log.root(request.first).await?),
+        second_root: hex::encode(ctx.log.root(second).await?),
+        path: path.iter().map(hex::encode).collect(),
+    }))
+}
+
+async fn find_head(ctx: &Context, tree_size: Option<u64>) -> HttpResult<TreeHead> {
+    ctx.log.head(tree_size).await?.ok_or_else(|| AppError::NotFound("No anchored tree head found".into()))
+}
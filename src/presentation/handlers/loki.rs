@@ -0,0 +1,188 @@
+use crate::{
+    context::Context,
+    domain::Document,
+    presentation::error::{AppError, HttpResult},
+};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use prost::Message;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Wire types of Loki's `logproto.PushRequest`, as sent snappy-compressed by promtail.
+mod logproto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PushRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub streams: Vec<Stream>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Stream {
+        #[prost(string, tag = "1")]
+        pub labels: String,
+        #[prost(message, repeated, tag = "2")]
+        pub entries: Vec<Entry>,
+        #[prost(uint64, tag = "3")]
+        pub hash: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Entry {
+        #[prost(message, optional, tag = "1")]
+        pub timestamp: Option<Timestamp>,
+        #[prost(string, tag = "2")]
+        pub line: String,
+        #[prost(message, repeated, tag = "3")]
+        pub structured_metadata: Vec<LabelPair>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct LabelPair {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Timestamp {
+        #[prost(int64, tag = "1")]
+        pub seconds: i64,
+        #[prost(int32, tag = "2")]
+        pub nanos: i32,
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonPushRequest {
+    streams: Vec<JsonStream>,
+}
+
+#[derive(Deserialize)]
+struct JsonStream {
+    stream: BTreeMap<String, String>,
+    values: Vec<Vec<Value>>,
+}
+
+struct LogLine {
+    labels: BTreeMap<String, String>,
+    timestamp: DateTime<Utc>,
+    line: String,
+    metadata: BTreeMap<String, String>,
+}
+
+impl LogLine {
+    fn into_document(self) -> Document {
+        let mut document = Map::new();
+        document.insert("@timestamp".into(), self.timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true).into());
+        document.insert("message".into(), self.line.into());
+        document.insert("labels".into(), Value::Object(self.labels.into_iter().map(|(k, v)| (k, v.into())).collect()));
+        if !self.metadata.is_empty() {
+            let metadata = self.metadata.into_iter().map(|(k, v)| (k, v.into())).collect();
+            document.insert("structured_metadata".into(), Value::Object(metadata));
+        }
+        document
+    }
+}
+
+/// Loki push API. Accepts `application/json` and snappy-compressed protobuf
+/// (`application/x-protobuf`), turning every log line into one document.
+pub async fn push(State(ctx): State<Context>, headers: HeaderMap, body: Bytes) -> HttpResult<StatusCode> {
+    let content_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    let lines = if content_type.starts_with("application/json") { parse_json(&body)? } else { parse_protobuf(&body)? };
+
+    let documents = lines.into_iter().map(LogLine::into_document).collect();
+    ctx.ingest_all(documents).await.map_err(|err| AppError::from_channel(err, ctx.config.ingest.retry_after_secs))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn parse_protobuf(body: &[u8]) -> HttpResult<Vec<LogLine>> {
+    let decoded =
+        snap::raw::Decoder::new().decompress_vec(body).map_err(|err| AppError::BadRequest(format!("Invalid snappy payload: {}", err)))?;
+    let request = logproto::PushRequest::decode(decoded.as_slice())
+        .map_err(|err| AppError::BadRequest(format!("Invalid protobuf push request: {}", err)))?;
+
+    let mut lines = Vec::new();
+    for stream in request.streams {
+        let labels = parse_labels(&stream.labels).map_err(AppError::BadRequest)?;
+        for entry in stream.entries {
+            let timestamp = entry
+                .timestamp
+                .and_then(|ts| DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32))
+                .ok_or_else(|| AppError::BadRequest("Invalid entry timestamp".into()))?;
+            let metadata = entry.structured_metadata.into_iter().map(|pair| (pair.name, pair.value)).collect();
+            lines.push(LogLine { labels: labels.clone(), timestamp, line: entry.line, metadata });
+        }
+    }
+    Ok(lines)
+}
+
+fn parse_json(body: &[u8]) -> HttpResult<Vec<LogLine>> {
+    let request: JsonPushRequest =
+        serde_json::from_slice(body).map_err(|err| AppError::BadRequest(format!("Invalid JSON push request: {}", err)))?;
+
+    let mut lines = Vec::new();
+    for stream in request.streams {
+        for value in stream.values {
+            let (timestamp, line) = match value.as_slice() {
+                [Value::String(ts), Value::String(line), ..] => (ts, line),
+                _ => return Err(AppError::BadRequest("Each value must be [\"<unix epoch ns>\", \"<line>\"]".into())),
+            };
+            let nanos: i64 = timestamp.parse().map_err(|_| AppError::BadRequest(format!("Invalid timestamp `{}`", timestamp)))?;
+            let metadata = match value.get(2) {
+                Some(Value::Object(metadata)) => {
+                    metadata.iter().map(|(k, v)| (k.clone(), v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))).collect()
+                }
+                _ => BTreeMap::new(),
+            };
+            lines.push(LogLine {
+                labels: stream.stream.clone(),
+                timestamp: DateTime::from_timestamp_nanos(nanos),
+                line: line.clone(),
+                metadata,
+            });
+        }
+    }
+    Ok(lines)
+}
+
+/// Parses a Prometheus-style label set such as `{job="varlogs", filename="/var/log/syslog"}`.
+fn parse_labels(input: &str) -> Result<BTreeMap<String, String>, String> {
+    let inner = input.trim().strip_prefix('{').and_then(|s| s.strip_suffix('}')).ok_or_else(|| format!("Invalid labels `{}`", input))?;
+
+    let mut labels = BTreeMap::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=')).collect();
+        if chars.next() != Some('=') || chars.next() != Some('"') {
+            return Err(format!("Invalid labels `{}`", input));
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(c) => value.push(c),
+                    None => return Err(format!("Invalid labels `{}`", input)),
+                },
+                Some('"') => break,
+                Some(c) => value.push(c),
+                None => return Err(format!("Invalid labels `{}`", input)),
+            }
+        }
+        labels.insert(name.trim().to_string(), value);
+    }
+    Ok(labels)
+}
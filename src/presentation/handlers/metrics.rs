@@ -4,6 +4,5 @@ use crate::context::Context;
 
 pub async fn get_metrics(State(ctx): State<Context>) -> String {
     ctx.prom.batch_size.set(ctx.config.batch_size as f64);
-    let gather = ctx.prom.gather();
-    return gather;
+    ctx.prom.gather()
 }
@@ -1,5 +1,13 @@
+pub mod admin;
+pub mod batches;
+pub mod chain;
+pub mod correlation;
 pub mod document;
+pub mod elastic;
+pub mod log;
+pub mod loki;
 pub mod metrics;
+pub mod proof;
 pub mod signer;
 pub mod storage;
 pub mod ui;
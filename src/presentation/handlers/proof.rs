@@ -0,0 +1,107 @@
+use crate::{
+    context::Context,
+    domain::{ChainLink, Document, DocumentProof, HashSpec},
+    presentation::error::{AppError, HttpResult},
+};
+use anyhow::Context as AnyhowContext;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Position {
+    batch_id: String,
+    ord: usize,
+    batch_size: usize,
+    /// Leaf index in the transparency log, when the batch was appended to it.
+    log_index: Option<u64>,
+}
+
+/// Material linking the document to the batch content digest.
+#[derive(Serialize)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum ProofMaterial {
+    /// RFC 6962 audit path, siblings from the leaf up to the root.
+    Merkle { leaf: String, tree_size: usize, path: Vec<String>, root: String },
+    /// Partial digest before the document and the documents after it.
+    Chain { previous: Option<String>, following: Vec<Document>, digest: String },
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// The document leads to the digest anchored by the signer.
+    Authentic,
+    /// The signer holds no digest for the batch yet.
+    NotAnchored,
+    /// The document no longer leads to the anchored digest.
+    Tampered,
+}
+
+#[derive(Serialize)]
+pub struct GetDocumentProofResponse {
+    document: Document,
+    position: Position,
+    hash: HashSpec,
+    link: Option<ChainLink>,
+    proof: ProofMaterial,
+    /// Digest the signer is expected to hold: the content digest, bound to the
+    /// chain link for chained batches.
+    expected: String,
+    anchored: Option<String>,
+    verdict: Verdict,
+}
+
+/// Answers whether one stored document is authentic: the document with its proof up
+/// to the batch digest, and that digest compared with the one anchored by the signer.
+pub async fn get_document_proof(
+    State(ctx): State<Context>, Path((batch_id, ord)): Path<(String, usize)>,
+) -> HttpResult<Json<GetDocumentProofResponse>> {
+    let batch = ctx.storage.retrieve(&batch_id).await.context("An error occurrued when retrieving data from storage")?;
+    let Some(batch) = batch else {
+        return Err(AppError::NotFound("No records found for the given batch_id".into()));
+    };
+    let Some(document) = batch.documents.get(ord).cloned() else {
+        return Err(AppError::NotFound("No document found for the given audita_ord".into()));
+    };
+    let proof = ctx.hasher.proof(&batch.documents, ord, &batch.hash)?.context("Proof index out of range")?;
+
+    let expected = match &batch.link {
+        Some(link) => ctx.hasher.link(&batch.hash, &proof.digest(), link),
+        None => proof.digest(),
+    };
+    let anchored = ctx.signer.digest(&batch_id).await?;
+    let verdict = match anchored {
+        None => Verdict::NotAnchored,
+        Some(anchored) if anchored == expected && ctx.hasher.verify(&document, &proof, &batch.hash)? => Verdict::Authentic,
+        Some(_) => Verdict::Tampered,
+    };
+
+    let log_index = ctx.log.entry(&batch_id).await?.map(|entry| entry.start + ord as u64);
+    let proof = match proof {
+        DocumentProof::Merkle(proof) => ProofMaterial::Merkle {
+            leaf: hex::encode(proof.leaf),
+            tree_size: proof.tree_size,
+            path: proof.path.iter().map(hex::encode).collect(),
+            root: hex::encode(proof.root),
+        },
+        DocumentProof::Chain(proof) => ProofMaterial::Chain {
+            previous: proof.previous.map(hex::encode),
+            following: proof.following,
+            digest: hex::encode(proof.digest),
+        },
+    };
+
+    Ok(Json(GetDocumentProofResponse {
+        document,
+        position: Position { batch_id, ord, batch_size: batch.documents.len(), log_index },
+        hash: batch.hash,
+        link: batch.link,
+        proof,
+        expected: hex::encode(expected),
+        anchored: anchored.map(hex::encode),
+        verdict,
+    }))
+}
@@ -1,22 +1,99 @@
 use crate::{
     context::Context,
+    domain::{AnchorReceipt, HashAlgorithm, SignerAudit},
+    infra::helper::{aggregate_leaf, root_from_path},
     presentation::error::{AppError, HttpResult},
 };
 use axum::{
     extract::{Path, State},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 
 #[derive(Serialize, Clone)]
 pub struct GetHashSignerResponse {
     id: String,
     hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anchor: Option<AnchorReceipt>,
 }
 
 pub async fn get_hash_signer(State(ctx): State<Context>, Path(id): Path<String>) -> HttpResult<Json<GetHashSignerResponse>> {
-    match ctx.signer.digest(&id).await? {
-        Some(digest) => Ok(Json(GetHashSignerResponse { id, hash: hex::encode(digest) })),
-        None => Err(AppError::NotFound("No records found for the given batch_id".into())),
+    let Some(digest) = ctx.signer.digest(&id).await? else {
+        return Err(AppError::NotFound("No records found for the given batch_id".into()));
+    };
+    let anchor = ctx.signer.anchor(&id).await?;
+    Ok(Json(GetHashSignerResponse { id, hash: hex::encode(digest), anchor }))
+}
+
+/// Transaction that anchored a batch, for looking it up independently on chain.
+pub async fn get_anchor_signer(State(ctx): State<Context>, Path(id): Path<String>) -> HttpResult<Json<AnchorReceipt>> {
+    match ctx.signer.anchor(&id).await? {
+        Some(anchor) => Ok(Json(anchor)),
+        None => Err(AppError::NotFound("No anchoring transaction found for the given batch_id".into())),
     }
 }
+
+/// Checks the records kept by signers that anchor locally, such as the `file` signer.
+pub async fn audit_signer(State(ctx): State<Context>) -> HttpResult<Json<SignerAudit>> {
+    match ctx.signer.audit().await? {
+        Some(audit) => Ok(Json(audit)),
+        None => Err(AppError::BadRequest("The configured signer keeps no local records to audit".into())),
+    }
+}
+
+/// RFC 3161 TimeStampResp anchoring a batch, for checking with `openssl ts -verify`.
+pub async fn get_token_signer(State(ctx): State<Context>, Path(id): Path<String>) -> HttpResult<Response> {
+    match ctx.signer.token(&id).await? {
+        Some(token) => Ok(([(CONTENT_TYPE, "application/timestamp-reply")], token).into_response()),
+        None => Err(AppError::NotFound("No timestamp token found for the given batch_id".into())),
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReceiptResponse {
+    batch_id: String,
+    digest: String,
+    aggregate_id: String,
+    index: usize,
+    tree_size: usize,
+    leaf: String,
+    path: Vec<String>,
+    root: String,
+    algorithm: HashAlgorithm,
+    anchored_at: DateTime<Utc>,
+    anchored: Option<String>,
+    verified: bool,
+}
+
+/// Path from a batch to the aggregate root anchored on its behalf, when the signer
+/// aggregates batches. It is only verified when the root recomputed from the batch id,
+/// digest and path is the digest the signer actually anchored.
+pub async fn get_receipt_signer(State(ctx): State<Context>, Path(id): Path<String>) -> HttpResult<Json<ReceiptResponse>> {
+    let Some(receipt) = ctx.signer.receipt(&id).await? else {
+        return Err(AppError::NotFound("No aggregate receipt found for the given batch_id".into()));
+    };
+    let proof = &receipt.proof;
+    let anchored = ctx.signer.digest(&receipt.aggregate_id).await?;
+    let leaf = aggregate_leaf(receipt.algorithm, &receipt.batch_id, &receipt.digest);
+    let root = root_from_path(receipt.algorithm, proof.index, proof.tree_size, leaf, &proof.path);
+    let verified = proof.leaf == leaf && root.is_some() && root == anchored;
+
+    Ok(Json(ReceiptResponse {
+        batch_id: receipt.batch_id,
+        digest: hex::encode(receipt.digest),
+        aggregate_id: receipt.aggregate_id,
+        index: proof.index,
+        tree_size: proof.tree_size,
+        leaf: hex::encode(proof.leaf),
+        path: proof.path.iter().map(hex::encode).collect(),
+        root: hex::encode(proof.root),
+        algorithm: receipt.algorithm,
+        anchored_at: receipt.anchored_at,
+        anchored: anchored.map(hex::encode),
+        verified,
+    }))
+}
@@ -1,6 +1,6 @@
 use crate::{
     context::Context,
-    domain::{Query, QueryResult},
+    domain::{HashSpec, Query, QueryResult},
     presentation::error::{AppError, HttpResult},
 };
 use anyhow::Context as AnyhowContext;
@@ -18,6 +18,8 @@ pub type CacheHashStorageResponse = Arc<Cache<String, GetHashStorageResponse>>;
 pub struct GetHashStorageResponse {
     id: String,
     hash: String,
+    hashed_with: HashSpec,
+    documents: usize,
 }
 
 pub async fn get_hash_storage(
@@ -27,11 +29,16 @@ pub async fn get_hash_storage(
         return Ok(Json(cached));
     }
 
-    let batch = ctx.storage.retrieve(&id).await.context("An error occurrued when retrieving data from storage")?;
+    let stored = ctx.storage.digest(&id).await.context("An error occurrued when retrieving data from storage")?;
 
-    match batch {
-        Some(batch) => {
-            let response = GetHashStorageResponse { id: id.clone(), hash: hex::encode(batch.digest) };
+    match stored {
+        Some(stored) => {
+            let response = GetHashStorageResponse {
+                id: id.clone(),
+                hash: hex::encode(stored.digest),
+                hashed_with: stored.hash,
+                documents: stored.count,
+            };
             cache.insert(id, response.clone()).await;
             Ok(Json(response))
         }
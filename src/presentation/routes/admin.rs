@@ -0,0 +1,15 @@
+use crate::{
+    context::Context,
+    presentation::handlers::admin::{get_dead_letter, list_dead_letters, redrive_dead_letter},
+};
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+pub fn routes() -> Router<Context> {
+    Router::new()
+        .route("/dead-letters", get(list_dead_letters))
+        .route("/dead-letters/{stage}/{id}", get(get_dead_letter))
+        .route("/dead-letters/{stage}/{id}/redrive", post(redrive_dead_letter))
+}
@@ -0,0 +1,6 @@
+use crate::{context::Context, presentation::handlers::batches::get_batch_status};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<Context> {
+    Router::new().route("/{id}/status", get(get_batch_status))
+}
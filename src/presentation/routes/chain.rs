@@ -0,0 +1,6 @@
+use crate::{context::Context, presentation::handlers::chain::verify_chain};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<Context> {
+    Router::new().route("/verify", get(verify_chain))
+}
@@ -0,0 +1,6 @@
+use crate::{context::Context, presentation::handlers::correlation::nat_attribution};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<Context> {
+    Router::new().route("/nat", get(nat_attribution))
+}
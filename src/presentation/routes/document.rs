@@ -1,6 +1,12 @@
-use crate::{context::Context, presentation::handlers::document::submit_document};
-use axum::{routing::post, Router};
+use crate::{
+    config::AppConfig,
+    context::Context,
+    presentation::handlers::document::{submit_bulk, submit_document},
+};
+use axum::{extract::DefaultBodyLimit, routing::post, Router};
 
-pub fn routes() -> Router<Context> {
-    Router::new().route("/", post(submit_document))
+pub fn routes(config: &AppConfig) -> Router<Context> {
+    Router::new()
+        .route("/", post(submit_document))
+        .route("/bulk", post(submit_bulk).layer(DefaultBodyLimit::max(config.ingest.max_body_bytes)))
 }
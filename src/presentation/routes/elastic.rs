@@ -0,0 +1,23 @@
+use crate::{
+    config::AppConfig,
+    context::Context,
+    presentation::handlers::elastic::{bulk, bulk_index, info, license, product_header},
+};
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware::map_response,
+    routing::{get, post},
+    Router,
+};
+use tower_http::decompression::RequestDecompressionLayer;
+
+pub fn routes(config: &AppConfig) -> Router<Context> {
+    Router::new()
+        .route("/", get(info))
+        .route("/_license", get(license))
+        .route("/_bulk", post(bulk).put(bulk))
+        .route("/{index}/_bulk", post(bulk_index).put(bulk_index))
+        .layer(DefaultBodyLimit::max(config.ingest.max_body_bytes))
+        .layer(RequestDecompressionLayer::new())
+        .layer(map_response(product_header))
+}
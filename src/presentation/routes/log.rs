@@ -0,0 +1,13 @@
+use crate::{
+    context::Context,
+    presentation::handlers::log::{get_consistency_proof, get_inclusion_proof, get_latest_tree_head, get_tree_head},
+};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<Context> {
+    Router::new()
+        .route("/sth", get(get_latest_tree_head))
+        .route("/sth/{tree_size}", get(get_tree_head))
+        .route("/proof/inclusion/{id}/{ord}", get(get_inclusion_proof))
+        .route("/proof/consistency", get(get_consistency_proof))
+}
@@ -0,0 +1,10 @@
+use crate::{config::AppConfig, context::Context, presentation::handlers::loki::push};
+use axum::{extract::DefaultBodyLimit, routing::post, Router};
+use tower_http::decompression::RequestDecompressionLayer;
+
+pub fn routes(config: &AppConfig) -> Router<Context> {
+    Router::new()
+        .route("/api/v1/push", post(push))
+        .layer(DefaultBodyLimit::max(config.ingest.max_body_bytes))
+        .layer(RequestDecompressionLayer::new())
+}
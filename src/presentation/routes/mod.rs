@@ -1,16 +1,32 @@
+pub mod admin;
+pub mod batches;
+pub mod chain;
+pub mod correlation;
 pub mod document;
+pub mod elastic;
+pub mod log;
+pub mod loki;
 pub mod metrics;
+pub mod proof;
 pub mod signer;
 pub mod storage;
 
-use crate::context::Context;
+use crate::{config::AppConfig, context::Context};
 use axum::{routing::get, Router};
 
-pub fn api() -> Router<Context> {
+pub fn api(config: &AppConfig) -> Router<Context> {
     Router::new()
-        .merge(document::routes())
+        .merge(document::routes(config))
         .nest("/signer", signer::routes())
         .nest("/storage", storage::routes())
+        .nest("/proof", proof::routes())
+        .nest("/batches", batches::routes())
+        .nest("/chain", chain::routes())
+        .nest("/log", log::routes())
+        .nest("/correlation", correlation::routes())
+        .nest("/admin", admin::routes())
+        .nest("/es", elastic::routes(config))
+        .nest("/loki", loki::routes(config))
         .nest("/metrics", metrics::routes())
         .route("/ping", get(ping))
 }
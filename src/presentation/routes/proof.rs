@@ -0,0 +1,6 @@
+use crate::{context::Context, presentation::handlers::proof::get_document_proof};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<Context> {
+    Router::new().route("/{batch_id}/{ord}", get(get_document_proof))
+}
@@ -1,6 +1,14 @@
-use crate::{context::Context, presentation::handlers::signer::get_hash_signer};
+use crate::{
+    context::Context,
+    presentation::handlers::signer::{audit_signer, get_anchor_signer, get_hash_signer, get_receipt_signer, get_token_signer},
+};
 use axum::{routing::get, Router};
 
 pub fn routes() -> Router<Context> {
-    Router::new().route("/hash/{id}", get(get_hash_signer))
+    Router::new()
+        .route("/hash/{id}", get(get_hash_signer))
+        .route("/token/{id}", get(get_token_signer))
+        .route("/receipt/{id}", get(get_receipt_signer))
+        .route("/anchor/{id}", get(get_anchor_signer))
+        .route("/audit", get(audit_signer))
 }
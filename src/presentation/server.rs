@@ -19,7 +19,7 @@ pub async fn run(ctx: Arc<Context>) -> Result<()> {
 fn router(ctx: Arc<Context>) -> Router {
     let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any);
     Router::new()
-        .nest("/api", routes::api())
+        .nest("/api", routes::api(&ctx.config))
         .fallback(ui)
         .layer(cors)
         .layer(NormalizePathLayer::trim_trailing_slash())
@@ -0,0 +1,46 @@
+use crate::{
+    context::Context,
+    domain::{Batch, Document},
+};
+use chrono::SecondsFormat;
+use std::{sync::Arc, time::Duration};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, error, info};
+
+/// Periodically takes a tree head of the transparency log and anchors it through the
+/// signer. A head is only recorded once anchored; a failed attempt is retried with a
+/// fresh head on the next tick.
+pub async fn run(ctx: Arc<Context>) {
+    let mut ticker = interval(Duration::from_secs(ctx.config.transparency.publish_interval_secs));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        if let Err(err) = publish(&ctx).await {
+            error!(?err, "Failed to anchor transparency log tree head");
+        }
+    }
+}
+
+async fn publish(ctx: &Context) -> anyhow::Result<()> {
+    ctx.prom.log_tree_size.set(ctx.log.size().await? as f64);
+    let Some(head) = ctx.log.checkpoint().await? else {
+        debug!("Transparency log has not grown since the last tree head");
+        return Ok(());
+    };
+
+    let mut document = Document::new();
+    document.insert("tree_size".into(), head.tree_size.into());
+    document.insert("root".into(), hex::encode(head.root).into());
+    document.insert("timestamp".into(), head.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true).into());
+    let batch = Batch { id: head.batch_id(), documents: vec![document], digest: head.digest, hash: ctx.log.spec(), link: None };
+
+    let start = std::time::Instant::now();
+    ctx.signer.publish(&batch).await?;
+    ctx.prom.signer_request_latency.observe(start.elapsed().as_secs_f64());
+    ctx.log.record(&head).await?;
+    ctx.prom.log_heads_total.inc();
+
+    info!(tree_size = head.tree_size, root = %hex::encode(head.root), "Anchored transparency log tree head");
+    Ok(())
+}
@@ -1,3 +1,7 @@
+pub mod log;
+pub mod retry;
 pub mod signer;
+pub mod status;
 pub mod storage;
+pub mod syslog;
 pub mod worker;
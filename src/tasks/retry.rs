@@ -0,0 +1,96 @@
+use crate::{
+    context::Context,
+    domain::{Batch, DeadLetter, RetryDecision, Stage},
+};
+use chrono::Utc;
+use std::{sync::Arc, time::Duration};
+use tokio::time::sleep;
+use tracing::{error, warn};
+
+/// Handles a failed signer or storage operation. The batch is re-enqueued for the
+/// same stage after a backoff delay, or moved to the dead-letter store once the
+/// retry budget is exhausted. The original delivery is only acknowledged after the
+/// batch is safely re-enqueued or dead-lettered; a batch the dead-letter store
+/// refuses is retried once more after the longest backoff instead.
+pub async fn schedule(ctx: Arc<Context>, stage: Stage, batch: Arc<Batch>, offset: u64, error: anyhow::Error) {
+    match ctx.retries.failed(stage, &batch.id) {
+        RetryDecision::Retry { attempt, delay } => {
+            ctx.prom.retries_total.inc();
+            warn!(%stage, batch_id = %batch.id, attempt, ?delay, "Scheduling batch retry");
+            requeue(ctx, stage, batch, offset, delay);
+        }
+        RetryDecision::Exhausted { attempts } => {
+            let letter = DeadLetter { stage, attempts, last_error: error.to_string(), failed_at: Utc::now(), batch: (*batch).clone() };
+            if let Err(err) = ctx.dead_letters.put(&letter).await {
+                let delay = Duration::from_millis(ctx.config.retry.max_delay_ms);
+                error!(?err, %stage, batch_id = %batch.id, ?delay, "Failed to store dead letter, scheduling batch retry");
+                requeue(ctx, stage, batch, offset, delay);
+                return;
+            }
+            ctx.prom.dead_letters_total.inc();
+            error!(%stage, batch_id = %batch.id, attempts, "Retry budget exhausted, batch moved to dead-letter store");
+            ack(&ctx, stage, offset).await;
+        }
+    }
+}
+
+/// Re-enqueues a batch for `stage` after `delay`, acknowledging the original delivery
+/// once it is back in the queue.
+fn requeue(ctx: Arc<Context>, stage: Stage, batch: Arc<Batch>, offset: u64, delay: Duration) {
+    tokio::spawn(async move {
+        sleep(delay).await;
+        if let Err(err) = enqueue(&ctx, stage, batch.clone()).await {
+            error!(?err, %stage, batch_id = %batch.id, "Failed to re-enqueue batch");
+            return;
+        }
+        ack(&ctx, stage, offset).await;
+    });
+}
+
+/// Puts a dead-lettered batch back into the queue of the stage that failed it. The
+/// dead letter is removed first and only the redrive that removed it enqueues the
+/// batch, so redriving twice does not process it twice; it is put back if the batch
+/// cannot be enqueued.
+pub async fn redrive(ctx: &Context, letter: DeadLetter) -> anyhow::Result<()> {
+    ctx.retries.reset(letter.stage, &letter.batch.id);
+    if !ctx.dead_letters.remove(letter.stage, &letter.batch.id).await? {
+        return Ok(());
+    }
+    if let Err(err) = enqueue(ctx, letter.stage, Arc::new(letter.batch.clone())).await {
+        ctx.dead_letters.put(&letter).await?;
+        return Err(err);
+    }
+    Ok(())
+}
+
+pub async fn ack(ctx: &Context, stage: Stage, offset: u64) {
+    let Some(channel) = ctx.pipeline.stage(stage) else { return };
+    if let Err(err) = channel.ack(offset).await {
+        error!(?err, %stage, offset, "Failed to acknowledge batch");
+    }
+}
+
+/// Puts a batch back into the queue of `stage`. Documents that never made it into a
+/// batch go back into the worker queue to be batched again, and a batch missing from
+/// the transparency log is appended to it directly.
+async fn enqueue(ctx: &Context, stage: Stage, batch: Arc<Batch>) -> anyhow::Result<()> {
+    match stage {
+        Stage::Signer => {
+            ctx.pipeline.signer.send(batch).await?;
+            ctx.prom.signer_queue_size.inc();
+        }
+        Stage::Storage => {
+            ctx.pipeline.storage.send(batch).await?;
+            ctx.prom.storage_queue_size.inc();
+        }
+        Stage::Worker => {
+            for document in &batch.documents {
+                ctx.ingest_wait(document.clone()).await?;
+            }
+        }
+        Stage::Log => {
+            ctx.log.append(&batch.id, &batch.documents).await?;
+        }
+    }
+    Ok(())
+}
@@ -1,24 +1,53 @@
-use crate::{context::Context, domain::Batch};
+use crate::{
+    context::Context,
+    domain::{Batch, BatchEvent, Delivery, Stage},
+    tasks::{retry, status::record},
+};
+use anyhow::Result;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tracing::{error, info, instrument, warn};
 
+/// Publishes batches as they arrive, as many at once as the signer takes. A single
+/// task drives the signer, so the limit holds for the whole process.
 pub async fn run(ctx: Arc<Context>) {
-    while let Some(batch) = ctx.pipeline.signer.recv().await {
+    let slots = Arc::new(Semaphore::new(ctx.signer.concurrency()));
+    loop {
+        let Ok(slot) = slots.clone().acquire_owned().await else { break };
+        let Some(Delivery { offset, item: batch }) = ctx.pipeline.signer.recv().await else { break };
         ctx.prom.signer_queue_size.dec();
-        let start = std::time::Instant::now();
-        send(ctx.clone(), batch).await;
-        ctx.prom.signer_request_latency.observe(start.elapsed().as_secs_f64());
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            let _slot = slot;
+            let start = std::time::Instant::now();
+            let sent = send(ctx.clone(), batch.clone()).await;
+            ctx.prom.signer_request_latency.observe(start.elapsed().as_secs_f64());
+
+            match sent {
+                Ok(()) => {
+                    ctx.retries.reset(Stage::Signer, &batch.id);
+                    retry::ack(&ctx, Stage::Signer, offset).await;
+                }
+                Err(err) => retry::schedule(ctx.clone(), Stage::Signer, batch, offset, err).await,
+            }
+        });
     }
     warn!("Signer worker shutting down: channel closed");
 }
 
 #[instrument(skip(ctx, batch), fields(batch_id = %batch.id))]
-async fn send(ctx: Arc<Context>, batch: Arc<Batch>) {
+async fn send(ctx: Arc<Context>, batch: Arc<Batch>) -> Result<()> {
     match ctx.signer.publish(&batch).await {
-        Ok(_) => info!("Batch successfully published by signer"),
+        Ok(_) => {
+            record(&ctx, &batch.id, BatchEvent::Anchored).await;
+            info!("Batch successfully published by signer");
+            Ok(())
+        }
         Err(err) => {
             ctx.prom.signer_errors_total.inc();
+            record(&ctx, &batch.id, BatchEvent::Failed { stage: Stage::Signer, error: err.to_string() }).await;
             error!(error = ?err, "Failed to publish batch");
+            Err(err)
         }
     }
 }
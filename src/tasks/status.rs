@@ -0,0 +1,8 @@
+use crate::{context::Context, domain::BatchEvent};
+use tracing::warn;
+
+pub async fn record(ctx: &Context, id: &str, event: BatchEvent) {
+    if let Err(err) = ctx.status.update(id, event).await {
+        warn!(error = ?err, batch_id = %id, "Failed to record batch status");
+    }
+}
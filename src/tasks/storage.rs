@@ -1,25 +1,44 @@
 use tracing::{error, info, instrument, warn};
 
-use crate::{context::Context, domain::Batch};
+use crate::{
+    context::Context,
+    domain::{Batch, BatchEvent, Delivery, Stage},
+    tasks::{retry, status::record},
+};
+use anyhow::Result;
 use std::sync::Arc;
 
 pub async fn run(ctx: Arc<Context>) {
-    while let Some(batch) = ctx.pipeline.storage.recv().await {
+    while let Some(Delivery { offset, item: batch }) = ctx.pipeline.storage.recv().await {
         ctx.prom.storage_queue_size.dec();
         let start = std::time::Instant::now();
-        send(ctx.clone(), batch).await;
+        let sent = send(ctx.clone(), batch.clone()).await;
         ctx.prom.storage_request_latency.observe(start.elapsed().as_secs_f64());
+
+        match sent {
+            Ok(()) => {
+                ctx.retries.reset(Stage::Storage, &batch.id);
+                retry::ack(&ctx, Stage::Storage, offset).await;
+            }
+            Err(err) => retry::schedule(ctx.clone(), Stage::Storage, batch, offset, err).await,
+        }
     }
     warn!("Storage worker shutting down: channel closed");
 }
 
 #[instrument(skip(ctx, batch), fields(batch_id = %batch.id))]
-async fn send(ctx: Arc<Context>, batch: Arc<Batch>) {
+async fn send(ctx: Arc<Context>, batch: Arc<Batch>) -> Result<()> {
     match ctx.storage.store(&batch).await {
-        Ok(_) => info!("Batch successfully stored"),
+        Ok(_) => {
+            record(&ctx, &batch.id, BatchEvent::Stored).await;
+            info!("Batch successfully stored");
+            Ok(())
+        }
         Err(err) => {
             ctx.prom.storage_errors_total.inc();
+            record(&ctx, &batch.id, BatchEvent::Failed { stage: Stage::Storage, error: err.to_string() }).await;
             error!(error = ?err, "Failed to store batch");
+            Err(err)
         }
     }
 }
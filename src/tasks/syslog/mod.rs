@@ -0,0 +1,124 @@
+mod parser;
+
+use crate::context::Context;
+use chrono::Utc;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    net::{TcpListener, TcpStream, UdpSocket},
+};
+use tracing::{debug, error, info, warn};
+
+/// Receives one message per datagram. UDP senders cannot be slowed down, so a
+/// message that does not fit in the worker queue within the ingest timeout is dropped.
+pub async fn udp(ctx: Arc<Context>, addr: String) {
+    let socket = match UdpSocket::bind(&addr).await {
+        Ok(socket) => socket,
+        Err(err) => {
+            error!(%addr, error = ?err, "Failed to bind syslog UDP listener");
+            return;
+        }
+    };
+    info!(%addr, "Syslog UDP listener started");
+
+    let listener = format!("udp/{}", addr);
+    let mut buf = vec![0u8; ctx.config.syslog.max_message_bytes];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                warn!(%addr, error = ?err, "Failed to receive syslog datagram");
+                continue;
+            }
+        };
+        let document = parse(&ctx, &listener, &buf[..len], peer);
+        if let Err(err) = ctx.ingest(document).await {
+            ctx.prom.syslog_dropped_total.with_label_values(&[&listener]).inc();
+            warn!(%peer, error = %err, "Dropped syslog message");
+        }
+    }
+}
+
+pub async fn tcp(ctx: Arc<Context>, addr: String) {
+    let socket = match TcpListener::bind(&addr).await {
+        Ok(socket) => socket,
+        Err(err) => {
+            error!(%addr, error = ?err, "Failed to bind syslog TCP listener");
+            return;
+        }
+    };
+    info!(%addr, "Syslog TCP listener started");
+
+    let listener = format!("tcp/{}", addr);
+    loop {
+        match socket.accept().await {
+            Ok((stream, peer)) => {
+                tokio::spawn(connection(ctx.clone(), listener.clone(), stream, peer));
+            }
+            Err(err) => warn!(%addr, error = ?err, "Failed to accept syslog connection"),
+        }
+    }
+}
+
+/// Reads RFC 6587 frames from one connection: octet-counted (`<len> <msg>`) when
+/// a frame starts with a digit, newline-delimited otherwise. Waiting for room in the
+/// worker queue stalls the reader, which pushes back on the sender through TCP.
+async fn connection(ctx: Arc<Context>, listener: String, stream: TcpStream, peer: SocketAddr) {
+    debug!(%peer, "Syslog connection opened");
+    let max = ctx.config.syslog.max_message_bytes;
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let frame = match read_frame(&mut reader, max).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(err) => {
+                warn!(%peer, error = %err, "Closing syslog connection");
+                break;
+            }
+        };
+        if frame.is_empty() {
+            continue;
+        }
+
+        let document = parse(&ctx, &listener, &frame, peer);
+        if let Err(err) = ctx.ingest_wait(document).await {
+            ctx.prom.syslog_dropped_total.with_label_values(&[&listener]).inc();
+            error!(%peer, error = ?err, "Dropped syslog message");
+            break;
+        }
+    }
+    debug!(%peer, "Syslog connection closed");
+}
+
+async fn read_frame(reader: &mut BufReader<TcpStream>, max: usize) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(&first) = reader.fill_buf().await?.first() else { return Ok(None) };
+
+    if first.is_ascii_digit() {
+        let mut header = Vec::new();
+        (&mut *reader).take(11).read_until(b' ', &mut header).await?;
+        let len: usize = std::str::from_utf8(header.strip_suffix(b" ").unwrap_or_default())?.parse()?;
+        if len > max {
+            anyhow::bail!("frame of {} bytes exceeds the {} byte limit", len, max);
+        }
+        let mut frame = vec![0u8; len];
+        reader.read_exact(&mut frame).await?;
+        return Ok(Some(frame));
+    }
+
+    let mut frame = Vec::new();
+    (&mut *reader).take(max as u64 + 1).read_until(b'\n', &mut frame).await?;
+    if frame.len() > max {
+        anyhow::bail!("line exceeds the {} byte limit", max);
+    }
+    Ok(Some(frame))
+}
+
+fn parse(ctx: &Context, listener: &str, bytes: &[u8], peer: SocketAddr) -> crate::domain::Document {
+    ctx.prom.syslog_messages_total.with_label_values(&[listener]).inc();
+    let message = parser::parse(&String::from_utf8_lossy(bytes));
+    if !message.is_well_formed() {
+        ctx.prom.syslog_parse_errors_total.with_label_values(&[listener]).inc();
+    }
+    message.into_document(peer.ip(), Utc::now())
+}
@@ -0,0 +1,249 @@
+use crate::domain::Document;
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeDelta, TimeZone, Utc};
+use serde_json::{Map, Value};
+use std::net::IpAddr;
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+const SEVERITIES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Rfc5424,
+    Rfc3164,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyslogMessage {
+    pub format: Format,
+    pub priority: Option<u8>,
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    pub host: Option<String>,
+    pub app: Option<String>,
+    pub procid: Option<String>,
+    pub msgid: Option<String>,
+    pub structured_data: Map<String, Value>,
+    pub message: String,
+}
+
+impl SyslogMessage {
+    /// Whether the header was fully understood rather than recovered leniently.
+    pub fn is_well_formed(&self) -> bool {
+        self.priority.is_some() && self.timestamp.is_some()
+    }
+
+    pub fn into_document(self, source: IpAddr, received_at: DateTime<Utc>) -> Document {
+        let mut document = Map::new();
+        let timestamp = self.timestamp.map(|ts| ts.with_timezone(&Utc)).unwrap_or(received_at);
+        document.insert("@timestamp".into(), timestamp.to_rfc3339_opts(SecondsFormat::Millis, true).into());
+        document.insert("source_ip".into(), source.to_string().into());
+        let format = match self.format {
+            Format::Rfc5424 => "rfc5424",
+            Format::Rfc3164 => "rfc3164",
+        };
+        document.insert("syslog_format".into(), format.into());
+
+        if let Some(priority) = self.priority {
+            document.insert("priority".into(), priority.into());
+            document.insert("facility".into(), FACILITIES[(priority >> 3) as usize].into());
+            document.insert("severity".into(), SEVERITIES[(priority & 7) as usize].into());
+        }
+        for (key, value) in [("host", self.host), ("app", self.app), ("procid", self.procid), ("msgid", self.msgid)] {
+            if let Some(value) = value {
+                document.insert(key.into(), value.into());
+            }
+        }
+        if !self.structured_data.is_empty() {
+            document.insert("structured_data".into(), Value::Object(self.structured_data));
+        }
+        document.insert("message".into(), self.message.into());
+        document
+    }
+}
+
+/// Parses an RFC 5424 or RFC 3164 message. Parsing never fails: whatever cannot be
+/// recognized as a header is kept in `message`.
+pub fn parse(input: &str) -> SyslogMessage {
+    let input = input.trim_end_matches(['\r', '\n', '\0']);
+    let (priority, rest) = parse_priority(input);
+
+    if let Some(message) = rest.strip_prefix("1 ").and_then(|rest| parse_rfc5424(priority, rest)) {
+        return message;
+    }
+    parse_rfc3164(priority, rest)
+}
+
+fn parse_priority(input: &str) -> (Option<u8>, &str) {
+    let Some(rest) = input.strip_prefix('<') else { return (None, input) };
+    let Some(end) = rest.find('>').filter(|end| (1..=3).contains(end)) else { return (None, input) };
+    match rest[..end].parse::<u8>() {
+        Ok(priority) if priority < 192 => (Some(priority), &rest[end + 1..]),
+        _ => (None, input),
+    }
+}
+
+fn nil(value: &str) -> Option<String> {
+    (value != "-").then(|| value.to_string())
+}
+
+fn parse_rfc5424(priority: Option<u8>, input: &str) -> Option<SyslogMessage> {
+    let mut fields = input.splitn(6, ' ');
+    let timestamp = fields.next()?;
+    let host = fields.next()?;
+    let app = fields.next()?;
+    let procid = fields.next()?;
+    let msgid = fields.next()?;
+    let rest = fields.next().unwrap_or("-");
+
+    let timestamp = match timestamp {
+        "-" => None,
+        ts => Some(DateTime::parse_from_rfc3339(ts).ok()?),
+    };
+    let (structured_data, message) = parse_structured_data(rest)?;
+    let message = message.strip_prefix(' ').unwrap_or(message);
+    let message = message.strip_prefix('\u{feff}').unwrap_or(message);
+
+    Some(SyslogMessage {
+        format: Format::Rfc5424,
+        priority,
+        timestamp,
+        host: nil(host),
+        app: nil(app),
+        procid: nil(procid),
+        msgid: nil(msgid),
+        structured_data,
+        message: message.to_string(),
+    })
+}
+
+/// Parses `[id name="value" ...][id ...]`, returning the elements and the remaining message.
+fn parse_structured_data(input: &str) -> Option<(Map<String, Value>, &str)> {
+    let mut elements = Map::new();
+    if let Some(rest) = input.strip_prefix('-') {
+        return Some((elements, rest));
+    }
+
+    let mut rest = input;
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        let mut params = Map::new();
+        let mut cursor = &element[id_end..];
+
+        loop {
+            cursor = cursor.trim_start_matches(' ');
+            if let Some(after) = cursor.strip_prefix(']') {
+                cursor = after;
+                break;
+            }
+            let eq = cursor.find('=')?;
+            let name = &cursor[..eq];
+            let mut chars = cursor[eq + 1..].strip_prefix('"')?.char_indices();
+            let mut value = String::new();
+            let consumed = loop {
+                match chars.next()? {
+                    (_, '\\') => {
+                        let (_, escaped) = chars.next()?;
+                        if !matches!(escaped, '"' | '\\' | ']') {
+                            value.push('\\');
+                        }
+                        value.push(escaped);
+                    }
+                    (i, '"') => break i + 1,
+                    (_, c) => value.push(c),
+                }
+            };
+            params.insert(name.to_string(), value.into());
+            cursor = &cursor[eq + 2 + consumed..];
+        }
+
+        elements.insert(id.to_string(), Value::Object(params));
+        rest = cursor;
+    }
+
+    if elements.is_empty() {
+        return None;
+    }
+    Some((elements, rest))
+}
+
+fn parse_rfc3164(priority: Option<u8>, input: &str) -> SyslogMessage {
+    let mut message = SyslogMessage {
+        format: Format::Rfc3164,
+        priority,
+        timestamp: None,
+        host: None,
+        app: None,
+        procid: None,
+        msgid: None,
+        structured_data: Map::new(),
+        message: input.to_string(),
+    };
+
+    let Some(timestamp) = input.get(..15).and_then(parse_bsd_timestamp) else { return message };
+    message.timestamp = Some(timestamp);
+    let mut rest = input[15..].trim_start();
+
+    if let Some((host, after)) = rest.split_once(' ') {
+        if !host.ends_with(':') && !host.contains('[') {
+            message.host = Some(host.to_string());
+            rest = after;
+        }
+    }
+
+    let tag_end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))).unwrap_or(rest.len());
+    let (tag, after) = rest.split_at(tag_end);
+    if !tag.is_empty() {
+        if let Some((procid, after)) = after.strip_prefix('[').and_then(|after| after.split_once("]:")) {
+            message.app = Some(tag.to_string());
+            message.procid = Some(procid.to_string());
+            rest = after;
+        } else if let Some(after) = after.strip_prefix(':') {
+            message.app = Some(tag.to_string());
+            rest = after;
+        }
+    }
+
+    message.message = rest.trim_start().to_string();
+    message
+}
+
+/// Parses `Mmm dd hh:mm:ss`, which carries neither year nor zone. The local zone is
+/// assumed, and a date more than a day ahead is taken to be from last year.
+fn parse_bsd_timestamp(input: &str) -> Option<DateTime<FixedOffset>> {
+    let now = Local::now();
+    let resolve = |year: i32| {
+        let naive = NaiveDateTime::parse_from_str(&format!("{} {}", year, input), "%Y %b %e %H:%M:%S").ok()?;
+        Local.from_local_datetime(&naive).earliest()
+    };
+
+    let mut timestamp = resolve(now.year())?;
+    if timestamp > now + TimeDelta::days(1) {
+        timestamp = resolve(now.year() - 1)?;
+    }
+    Some(timestamp.fixed_offset())
+}
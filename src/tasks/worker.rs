@@ -2,52 +2,206 @@ use tracing::{debug, error, instrument, warn};
 
 use crate::{
     context::Context,
-    domain::{Batch, Document},
+    domain::{Batch, BatchStatus, DeadLetter, Delivery, Document, RetryDecision, Stage},
 };
-use std::sync::Arc;
+use anyhow::Result;
+use chrono::Utc;
+use std::{future::pending, sync::Arc, time::Duration};
+use tokio::time::{sleep, sleep_until, Instant};
+
+/// Documents waiting to be sealed into the next batch.
+#[derive(Default)]
+struct Buffer {
+    documents: Vec<Document>,
+    offsets: Vec<u64>,
+    /// Id of the batch being sealed, kept across failed attempts so they leave no
+    /// trace under other ids.
+    id: Option<String>,
+    /// Backoff before sealing again after a failed attempt.
+    retry: Option<Duration>,
+}
+
+impl Buffer {
+    fn push(&mut self, document: Document, offset: u64) {
+        self.documents.push(document);
+        self.offsets.push(offset);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// When the documents left after a seal are sealed again: after the retry backoff
+    /// when the seal failed, without waiting for new input.
+    fn deadline(&self, max_wait: Duration) -> Option<Instant> {
+        (!self.is_empty()).then(|| Instant::now() + self.retry.unwrap_or(max_wait))
+    }
+}
 
 pub async fn run(ctx: Arc<Context>) {
-    let mut buffer = Vec::new();
+    let mut buffer = Buffer::default();
+    let max_wait = Duration::from_millis(ctx.config.batch_max_wait_ms);
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let timeout = async {
+            match deadline {
+                Some(deadline) => sleep_until(deadline).await,
+                None => pending().await,
+            }
+        };
+
+        // A buffer that failed to seal takes no new documents, so it cannot grow while
+        // it waits for its retry; the other workers keep draining the queue.
+        tokio::select! {
+            document = ctx.pipeline.worker.recv(), if buffer.retry.is_none() => {
+                let Some(Delivery { offset, item: document }) = document else { break };
+                ctx.prom.worker_queue_size.dec();
+                debug!("Received document for batching");
 
-    while let Some(document) = ctx.pipeline.worker.recv().await {
-        ctx.prom.worker_queue_size.dec();
-        debug!("Received document for batching");
-        buffer.push(document);
+                if buffer.is_empty() {
+                    deadline = Some(Instant::now() + max_wait);
+                }
+                buffer.push(document, offset);
 
-        if buffer.len() >= ctx.config.batch_size {
-            let start = std::time::Instant::now();
-            send(ctx.clone(), &mut buffer).await;
-            ctx.prom.batch_processing_latency.observe(start.elapsed().as_secs_f64());
+                if buffer.len() >= ctx.config.batch_size {
+                    let start = std::time::Instant::now();
+                    if send(ctx.clone(), &mut buffer).await {
+                        ctx.prom.batches_sealed_by_size_total.inc();
+                    }
+                    ctx.prom.batch_processing_latency.observe(start.elapsed().as_secs_f64());
+                    deadline = buffer.deadline(max_wait);
+                }
+            }
+            _ = timeout => {
+                debug!("Batch wait time elapsed, sealing partial batch ({} docs)", buffer.len());
+                let start = std::time::Instant::now();
+                if send(ctx.clone(), &mut buffer).await {
+                    ctx.prom.batches_sealed_by_timeout_total.inc();
+                }
+                ctx.prom.batch_processing_latency.observe(start.elapsed().as_secs_f64());
+                deadline = buffer.deadline(max_wait);
+            }
         }
     }
 
-    if !buffer.is_empty() {
+    while !buffer.is_empty() {
         warn!("Final flush of remaining documents ({} docs)", buffer.len());
-        send(ctx.clone(), &mut buffer).await;
+        if !send(ctx.clone(), &mut buffer).await {
+            if let Some(delay) = buffer.retry {
+                sleep(delay).await;
+            }
+        }
     }
 
     warn!("Worker shutting down: channel closed");
 }
 
+/// Seals the buffered documents into a batch and hands it off. The buffer is only
+/// cleared, and its offsets acknowledged, once the hand-off succeeded. A failed
+/// attempt is retried under the retry policy, and once that is exhausted the
+/// documents are moved to the dead-letter store instead of stalling the worker.
 #[instrument(skip(ctx, buffer))]
-async fn send(ctx: Arc<Context>, buffer: &mut Vec<Document>) {
-    let id = ctx.uuid.generate();
-    let digest = match ctx.hasher.digest(&buffer) {
-        Ok(d) => d,
+async fn send(ctx: Arc<Context>, buffer: &mut Buffer) -> bool {
+    let id = buffer.id.get_or_insert_with(|| ctx.uuid.generate()).clone();
+
+    let sealed = match seal(&ctx, &id, &buffer.documents).await {
+        Ok(()) => {
+            ctx.retries.reset(Stage::Worker, &id);
+            true
+        }
         Err(err) => {
             ctx.prom.batches_error_total.inc();
-            error!(?err, "Failed to compute digest");
-            return;
+            match ctx.retries.failed(Stage::Worker, &id) {
+                RetryDecision::Retry { attempt, delay } => {
+                    ctx.prom.retries_total.inc();
+                    warn!(?err, batch_id = %id, attempt, ?delay, "Failed to seal batch, retrying");
+                    buffer.retry = Some(delay);
+                    return false;
+                }
+                RetryDecision::Exhausted { attempts } => {
+                    error!(?err, batch_id = %id, attempts, "Retry budget exhausted, documents moved to dead-letter store");
+                    let batch = Batch { id, documents: buffer.documents.clone(), digest: [0; 32], hash: ctx.hasher.spec(), link: None };
+                    park(&ctx, Stage::Worker, attempts, &err, batch).await;
+                    false
+                }
+            }
         }
     };
 
-    let batch = Arc::new(Batch { id, documents: std::mem::take(buffer), digest });
-    debug!(batch_id = %batch.id, count = batch.documents.len(), "Sending batch to signer and storage");
+    buffer.documents.clear();
+    buffer.id = None;
+    buffer.retry = None;
+    for offset in std::mem::take(&mut buffer.offsets) {
+        if let Err(err) = ctx.pipeline.worker.ack(offset).await {
+            error!(?err, offset, "Failed to acknowledge document");
+        }
+    }
+    sealed
+}
 
-    ctx.pipeline.signer.send(batch.clone()).await;
-    ctx.pipeline.storage.send(batch.clone()).await;
+/// Hands a batch of `documents` to the signer and storage. Fails only while nothing
+/// has been handed off yet; once the signer holds the batch, sealing it again would
+/// anchor the same documents twice, so later failures are dead-lettered instead.
+async fn seal(ctx: &Context, id: &str, documents: &[Document]) -> Result<()> {
+    let hash = ctx.hasher.spec();
+    let content = ctx.hasher.digest(documents, &hash)?;
+
+    let pending = ctx.chain.link(&hash, &content).await?;
+    let created_at = pending.link.created_at;
+    let batch = Arc::new(Batch {
+        id: id.to_string(),
+        documents: documents.to_vec(),
+        digest: pending.digest,
+        hash,
+        link: Some(pending.link.clone()),
+    });
+    debug!(batch_id = %batch.id, count = batch.documents.len(), "Sending batch to signer and storage");
 
+    // The chain stays locked from the link to the commit, so nothing but the signer
+    // hand-off belongs in between.
+    ctx.pipeline.signer.send(batch.clone()).await?;
+    if let Err(err) = pending.commit().await {
+        error!(?err, batch_id = %batch.id, "Failed to persist chain head");
+    }
     ctx.prom.signer_queue_size.inc();
-    ctx.prom.storage_queue_size.inc();
+
+    if let Err(err) = ctx.status.create(&BatchStatus::new(batch.id.clone(), created_at)).await {
+        warn!(?err, batch_id = %batch.id, "Failed to record batch status");
+    }
+
+    match ctx.pipeline.storage.send(batch.clone()).await {
+        Ok(()) => ctx.prom.storage_queue_size.inc(),
+        Err(err) => {
+            error!(?err, batch_id = %batch.id, "Failed to send batch to storage");
+            park(ctx, Stage::Storage, 0, &err, (*batch).clone()).await;
+        }
+    }
     ctx.prom.batches_total.inc();
+
+    // Only batches that made it into the pipeline enter the transparency log. A batch
+    // the log refuses is dead-lettered, so redriving it appends it later.
+    if let Err(err) = ctx.log.append(&batch.id, &batch.documents).await {
+        ctx.prom.batches_error_total.inc();
+        error!(?err, batch_id = %batch.id, "Failed to append batch to the transparency log");
+        park(ctx, Stage::Log, 0, &err, (*batch).clone()).await;
+    }
+    Ok(())
+}
+
+/// Moves a batch to the dead-letter store, retrying for as long as the store fails:
+/// the batch exists nowhere else once its documents are acknowledged.
+async fn park(ctx: &Context, stage: Stage, attempts: u32, error: &anyhow::Error, batch: Batch) {
+    let letter = DeadLetter { stage, attempts, last_error: error.to_string(), failed_at: Utc::now(), batch };
+    let mut delay = ctx.config.retry.base_delay_ms;
+    while let Err(err) = ctx.dead_letters.put(&letter).await {
+        error!(?err, %stage, batch_id = %letter.batch.id, "Failed to store dead letter, retrying");
+        sleep(Duration::from_millis(delay)).await;
+        delay = (delay * 2).min(ctx.config.retry.max_delay_ms);
+    }
+    ctx.prom.dead_letters_total.inc();
 }